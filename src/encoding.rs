@@ -9,11 +9,14 @@ use std::io::{self, Read, Write};
 use bytes::{BufMut, Bytes, BytesMut};
 use integer_encoding::{VarIntReader, VarIntWriter};
 use nebz::NonEmptyBz;
+use oblux::U63;
 
 use super::{NodeHash, NodeKey, SHA256_HASH_LEN};
 
 pub const NODE_DB_KEY_LEN: usize = size_of::<u8>() + size_of::<u64>() + size_of::<u32>();
 
+pub const VERSION_KEY_LEN: usize = size_of::<u8>() + size_of::<u64>();
+
 pub fn deserialize_hash<R>(mut reader: R) -> Result<NodeHash, DeserializationError>
 where
 	R: Read,
@@ -93,3 +96,17 @@ pub const fn make_ndb_key<const KEY_PREFIX_BYTE: u8>(nk: &NodeKey) -> [u8; NODE_
 
 	key
 }
+
+pub const fn make_version_key<const KEY_PREFIX_BYTE: u8>(version: U63) -> [u8; VERSION_KEY_LEN] {
+	let mut key = [0; VERSION_KEY_LEN];
+	key[0] = KEY_PREFIX_BYTE;
+
+	let version_be_bytes = version.get().to_be_bytes();
+	let mut i = 0;
+	while i < size_of::<u64>() {
+		key[i + 1] = version_be_bytes[i];
+		i += 1;
+	}
+
+	key
+}
@@ -27,6 +27,15 @@ pub enum DeserializationError {
 
 	#[error("invalid mode")]
 	InvalidMode,
+
+	#[error("unsupported compression error: tag {0:#04x}")]
+	UnsupportedCompression(u8),
+
+	#[error("checksum mismatch error: stored node must match its checksum")]
+	ChecksumMismatch,
+
+	#[error("value hash mismatch error: separated value must match its hash")]
+	ValueHashMismatch,
 }
 
 impl From<TryFromIntError> for DeserializationError {
@@ -5,9 +5,9 @@ use nebz::NonEmptyBz;
 use oblux::U63;
 
 use crate::{
-	Get, GetError, NodeHash, Sealed,
-	kvstore::KVStore,
-	node::{ArlockNode, NodeError, ndb::NodeDb},
+	Get, GetError, MutableTree, MutableTreeError, NodeHash, NodeKey, Sealed,
+	kvstore::{AsyncKVStore, KVIterator, KVStore},
+	node::{self, ArlockNode, NodeError, ndb::NodeDb},
 };
 
 #[derive(Debug, Clone)]
@@ -52,12 +52,65 @@ impl<DB> ImmutableTree<DB> {
 		self.version
 	}
 
-	pub(crate) fn set_version(&mut self, version: U63) {
-		self.version = version;
+	pub(crate) fn root(&self) -> &ArlockNode {
+		&self.root
 	}
+}
 
-	fn root(&self) -> &ArlockNode {
-		&self.root
+impl<DB> ImmutableTree<DB>
+where
+	DB: KVStore + KVIterator + Clone,
+{
+	/// Loads the latest committed version without ever writing to `db`, see
+	/// [`MutableTree::load_latest_version_read_only`].
+	///
+	/// Returns [`None`] if there is no such version or it is empty.
+	pub fn load_latest_version(db: DB) -> Result<Option<Self>, MutableTreeError> {
+		MutableTree::load_latest_version_read_only(db).map(MutableTree::into_last_saved)
+	}
+}
+
+impl<DB> ImmutableTree<DB>
+where
+	DB: KVStore,
+{
+	/// Gets the index and value of `key` along with the version its leaf was written in and the
+	/// leaf's [`NodeKey`].
+	#[allow(clippy::type_complexity)]
+	pub fn get_with_meta<K>(
+		&self,
+		key: NonEmptyBz<K>,
+	) -> Result<(U63, Option<(Bytes, U63, NodeKey)>), GetError>
+	where
+		K: AsRef<[u8]>,
+	{
+		let ndb = self.ndb.snapshot().map_err(NodeError::from)?;
+		let (idx, leaf) = self.root().read().map_err(NodeError::from)?.get_leaf(&ndb, key)?;
+
+		let leaf = leaf
+			.map(|(value, nk)| {
+				nk.map(|nk| (value, *nk.version(), nk)).ok_or(NodeError::Other(
+					"leaf of a saved tree must be saved".into(),
+				))
+			})
+			.transpose()?;
+
+		Ok((idx, leaf))
+	}
+}
+
+impl<DB> ImmutableTree<DB>
+where
+	DB: AsyncKVStore,
+{
+	/// Asynchronous counterpart of [`Get::get`].
+	pub async fn get_async<K>(&self, key: NonEmptyBz<K>) -> Result<(U63, Option<Bytes>), GetError>
+	where
+		K: AsRef<[u8]>,
+	{
+		let ndb = self.ndb.snapshot_async().await.map_err(NodeError::from)?;
+
+		node::get_async(self.root().clone(), &ndb, key).await.map_err(From::from)
 	}
 }
 
@@ -73,7 +126,10 @@ where
 	where
 		K: AsRef<[u8]>,
 	{
-		self.root().read().map_err(NodeError::from)?.get(&self.ndb, key).map_err(From::from)
+		// the whole traversal reads from one snapshot, unaffected by concurrent writes
+		let ndb = self.ndb.snapshot().map_err(NodeError::from)?;
+
+		self.root().read().map_err(NodeError::from)?.get(&ndb, key).map_err(From::from)
 	}
 }
 
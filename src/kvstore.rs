@@ -1,6 +1,36 @@
+pub mod memory;
+pub mod prefix;
+
+#[cfg(feature = "encryption")]
+pub mod encrypted;
+
+#[cfg(feature = "test-utils")]
+pub mod faulty;
+
+#[cfg(feature = "lmdb")]
+pub mod lmdb;
+
 #[cfg(feature = "redb")]
 pub mod redb;
 
+#[cfg(feature = "rocksdb")]
+pub mod rocksdb;
+
+#[cfg(feature = "sled")]
+pub mod sled;
+
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+mod asynchronous;
+mod batch;
+mod chunked;
+
+pub use self::{
+	asynchronous::{AsyncKVIterator, AsyncKVStore, AsyncMutKVStore},
+	batch::{WriteBatch, WriteOp},
+};
+
 use core::{error::Error, ops::RangeBounds};
 
 use bytes::Bytes;
@@ -17,11 +47,37 @@ pub trait MutKVStore {
 	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
 	where
 		K: AsRef<[u8]>;
+
+	/// Applies the writes of `batch` in order.
+	///
+	/// The default implementation applies them one at a time and is thus not atomic, stores
+	/// supporting transactions should override it to apply either all or none of them.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => self.insert(key, value)?,
+				WriteOp::Remove { key } => self.remove(key)?,
+			};
+		}
+
+		Ok(())
+	}
 }
 
 pub trait KVStore {
 	type Error: Error + Send + Sync + 'static;
 
+	type Snapshot: KVStore<Error = Self::Error>;
+
+	/// Returns a read-only view of the store as of now, unaffected by later writes where the
+	/// backend supports it.
+	///
+	/// Queries spanning several reads should run against a single snapshot to observe one
+	/// consistent state of the store. Backends without point-in-time reads, namely `SledStore`
+	/// and `SqliteStore`, return a handle to the live store instead, so queries on them may
+	/// observe concurrent writes.
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error>;
+
 	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
 	where
 		K: AsRef<[u8]>;
@@ -0,0 +1,95 @@
+use core::{error::Error, future::Future, ops::RangeBounds};
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+
+use super::{WriteBatch, WriteOp};
+
+/// Asynchronous counterpart of [`MutKVStore`](super::MutKVStore), for stores doing I/O.
+pub trait AsyncMutKVStore: Sync {
+	type Error: Error + Send + Sync + 'static;
+
+	fn insert<K, V>(
+		&self,
+		key: NonEmptyBz<K>,
+		value: NonEmptyBz<V>,
+	) -> impl Future<Output = Result<bool, Self::Error>> + Send
+	where
+		K: AsRef<[u8]> + Send,
+		V: AsRef<[u8]> + Send;
+
+	fn remove<K>(
+		&self,
+		key: NonEmptyBz<K>,
+	) -> impl Future<Output = Result<bool, Self::Error>> + Send
+	where
+		K: AsRef<[u8]> + Send;
+
+	/// Applies the writes of `batch` in order.
+	///
+	/// Like [`MutKVStore::write_batch`](super::MutKVStore::write_batch), the default
+	/// implementation is not atomic.
+	fn write_batch(
+		&self,
+		batch: WriteBatch,
+	) -> impl Future<Output = Result<(), Self::Error>> + Send {
+		async move {
+			for op in batch {
+				match op {
+					WriteOp::Insert { key, value } => self.insert(key, value).await?,
+					WriteOp::Remove { key } => self.remove(key).await?,
+				};
+			}
+
+			Ok(())
+		}
+	}
+}
+
+/// Asynchronous counterpart of [`KVStore`](super::KVStore), for stores doing I/O.
+pub trait AsyncKVStore: Sync {
+	type Error: Error + Send + Sync + 'static;
+
+	type Snapshot: AsyncKVStore<Error = Self::Error>;
+
+	/// Returns a read-only view of the store as of now, see
+	/// [`KVStore::snapshot`](super::KVStore::snapshot).
+	fn snapshot(&self) -> impl Future<Output = Result<Self::Snapshot, Self::Error>> + Send;
+
+	fn get<K>(
+		&self,
+		key: NonEmptyBz<K>,
+	) -> impl Future<Output = Result<Option<NonEmptyBz<Bytes>>, Self::Error>> + Send
+	where
+		K: AsRef<[u8]> + Send;
+
+	fn has<K>(&self, key: NonEmptyBz<K>) -> impl Future<Output = Result<bool, Self::Error>> + Send
+	where
+		K: AsRef<[u8]> + Send,
+	{
+		async move { self.get(key).await.map(|v| v.is_some()) }
+	}
+}
+
+/// Asynchronous counterpart of [`KVIterator`](super::KVIterator), for stores doing I/O.
+pub trait AsyncKVIterator: Sync {
+	type Error: Error + Send + Sync + 'static;
+
+	type FetchError: Error + Send + Sync + 'static;
+
+	/// Fetches the entries within `range`, which are then iterated without further I/O.
+	#[allow(clippy::type_complexity)]
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> impl Future<
+		Output = Result<
+			impl DoubleEndedIterator<
+				Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+			> + Send,
+			Self::Error,
+		>,
+	> + Send
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>> + Send;
+}
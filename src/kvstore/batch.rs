@@ -0,0 +1,66 @@
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+
+/// Ordered set of writes applied at once by [`MutKVStore::write_batch`].
+///
+/// [`MutKVStore::write_batch`]: super::MutKVStore::write_batch
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WriteBatch {
+	ops: Vec<WriteOp>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WriteOp {
+	Insert {
+		key: NonEmptyBz<Bytes>,
+		value: NonEmptyBz<Bytes>,
+	},
+	Remove {
+		key: NonEmptyBz<Bytes>,
+	},
+}
+
+impl WriteBatch {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	pub fn insert<K, V>(&mut self, key: NonEmptyBz<K>, value: NonEmptyBz<V>)
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let (key, value) = (key.as_ref_slice().into(), value.as_ref_slice().into());
+
+		self.ops.push(WriteOp::Insert { key, value });
+	}
+
+	pub fn remove<K>(&mut self, key: NonEmptyBz<K>)
+	where
+		K: AsRef<[u8]>,
+	{
+		self.ops.push(WriteOp::Remove { key: key.as_ref_slice().into() });
+	}
+
+	pub fn len(&self) -> usize {
+		self.ops.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.ops.is_empty()
+	}
+
+	pub fn ops(&self) -> &[WriteOp] {
+		&self.ops
+	}
+}
+
+impl IntoIterator for WriteBatch {
+	type Item = WriteOp;
+
+	type IntoIter = std::vec::IntoIter<WriteOp>;
+
+	fn into_iter(self) -> Self::IntoIter {
+		self.ops.into_iter()
+	}
+}
@@ -0,0 +1,169 @@
+use core::ops::{Bound, RangeBounds};
+
+use std::collections::VecDeque;
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+
+/// Number of entries stores are meant to read at once into a [`Chunk`].
+pub(crate) const CHUNK_LEN: usize = 256;
+
+type Entry = (NonEmptyBz<Bytes>, NonEmptyBz<Bytes>);
+
+type RawRange<'a> = (Bound<&'a [u8]>, Bound<&'a [u8]>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Direction {
+	Forward,
+	Reverse,
+}
+
+/// Entries read from one end of a range, in the order of the [`Direction`] they were read in.
+pub(crate) struct Chunk {
+	entries: Vec<Entry>,
+
+	/// Bound the range resumes from on that end, [`None`] if it was read to its other end.
+	resume: Option<Bound<Bytes>>,
+}
+
+/// Double-ended iterator over a key range, reading it a [`Chunk`] at a time from either end, so
+/// that neither the whole range is held in memory nor a read lasts beyond a chunk.
+///
+/// Chunks are read by `fetch` off the range left between both ends.
+pub(crate) struct ChunkedRange<F> {
+	range: (Bound<Bytes>, Bound<Bytes>),
+	front: VecDeque<Entry>,
+	back: VecDeque<Entry>,
+	exhausted: bool,
+	fetch: F,
+}
+
+impl Chunk {
+	/// Returns the chunk of `entries`, resuming past the key of the last one, or [`None`] if
+	/// there are none.
+	pub fn new(entries: Vec<Entry>) -> Option<Self> {
+		let last_key = entries.last()?.0.get().clone();
+
+		Some(Self { entries, resume: Some(Bound::Excluded(last_key)) })
+	}
+
+	/// Returns the chunk of `entries` resuming from `resume`, for stores reading more entries
+	/// than they yield.
+	#[cfg(feature = "encryption")]
+	pub fn resuming(entries: Vec<Entry>, resume: Option<Bound<Bytes>>) -> Self {
+		Self { entries, resume }
+	}
+}
+
+impl<F, E> ChunkedRange<F>
+where
+	F: FnMut(RawRange<'_>, Direction) -> Result<Option<Chunk>, E>,
+{
+	pub fn new<'a, KR>(range: KR, fetch: F) -> Self
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let bound =
+			|bound: Bound<&NonEmptyBz<&[u8]>>| bound.map(|key| Bytes::copy_from_slice(key.get()));
+
+		Self {
+			range: (bound(range.start_bound()), bound(range.end_bound())),
+			front: VecDeque::new(),
+			back: VecDeque::new(),
+			exhausted: false,
+			fetch,
+		}
+	}
+
+	/// Reads the next chunk towards `direction`, returning whether the range had any left.
+	fn fetch(&mut self, direction: Direction) -> Result<bool, E> {
+		if self.exhausted || is_empty(&self.range) {
+			self.exhausted = true;
+			return Ok(false);
+		}
+
+		let range = (
+			self.range.0.as_ref().map(Bytes::as_ref),
+			self.range.1.as_ref().map(Bytes::as_ref),
+		);
+
+		let Some(Chunk { entries, resume }) = (self.fetch)(range, direction)? else {
+			self.exhausted = true;
+			return Ok(false);
+		};
+
+		// the back buffer is kept in ascending order as well
+		match direction {
+			Direction::Forward => self.front.extend(entries),
+			Direction::Reverse => entries.into_iter().for_each(|entry| self.back.push_front(entry)),
+		}
+
+		match (resume, direction) {
+			(Some(resume), Direction::Forward) => self.range.0 = resume,
+			(Some(resume), Direction::Reverse) => self.range.1 = resume,
+			(None, _) => self.exhausted = true,
+		}
+
+		Ok(true)
+	}
+
+	fn fail(&mut self, err: E) -> Option<Result<Entry, E>> {
+		self.exhausted = true;
+		self.front.clear();
+		self.back.clear();
+
+		Some(Err(err))
+	}
+}
+
+impl<F, E> Iterator for ChunkedRange<F>
+where
+	F: FnMut(RawRange<'_>, Direction) -> Result<Option<Chunk>, E>,
+{
+	type Item = Result<Entry, E>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(entry) = self.front.pop_front() {
+				return Some(Ok(entry));
+			}
+
+			match self.fetch(Direction::Forward) {
+				Ok(true) => (),
+				// entries read from the back end are all that is left
+				Ok(false) => return self.back.pop_front().map(Ok),
+				Err(err) => return self.fail(err),
+			}
+		}
+	}
+}
+
+impl<F, E> DoubleEndedIterator for ChunkedRange<F>
+where
+	F: FnMut(RawRange<'_>, Direction) -> Result<Option<Chunk>, E>,
+{
+	fn next_back(&mut self) -> Option<Self::Item> {
+		loop {
+			if let Some(entry) = self.back.pop_back() {
+				return Some(Ok(entry));
+			}
+
+			match self.fetch(Direction::Reverse) {
+				Ok(true) => (),
+				// entries read from the front end are all that is left
+				Ok(false) => return self.front.pop_back().map(Ok),
+				Err(err) => return self.fail(err),
+			}
+		}
+	}
+}
+
+/// Returns whether `range` admits no key, which some stores would rather panic on.
+fn is_empty((start, end): &(Bound<Bytes>, Bound<Bytes>)) -> bool {
+	match (start, end) {
+		(Bound::Included(start) | Bound::Excluded(start), Bound::Excluded(end))
+		| (Bound::Excluded(start), Bound::Included(end)) => start >= end,
+		(Bound::Included(start), Bound::Included(end)) => start > end,
+		(Bound::Unbounded, _) | (_, Bound::Unbounded) => false,
+	}
+}
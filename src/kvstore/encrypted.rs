@@ -0,0 +1,447 @@
+mod error;
+
+pub use self::error::EncryptedStoreError;
+
+use core::{
+	fmt,
+	ops::{Bound, RangeBounds},
+};
+
+use std::sync::Arc;
+
+use aes_gcm_siv::{
+	Aes256GcmSiv, KeyInit, Nonce,
+	aead::{Aead, AeadCore, OsRng, Payload},
+};
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+use sha2::{Digest, Sha256};
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+	prefix::prefix_end,
+};
+
+const NONCE_LEN: usize = 12;
+
+/// A store encrypting the values of the wrapped store at rest, and optionally its keys.
+///
+/// Values are sealed with AES-256-GCM-SIV under a random nonce and bound to their key, so that
+/// entries cannot be swapped for one another. Keys stay in clear unless
+/// [key encryption](Self::with_key_encryption) is enabled.
+#[derive(Clone)]
+pub struct EncryptedStore<S> {
+	store: S,
+	ciphers: Arc<Ciphers>,
+	clear_prefix_len: Option<usize>,
+}
+
+struct Ciphers {
+	values: Aes256GcmSiv,
+	keys: Aes256GcmSiv,
+}
+
+impl<S> EncryptedStore<S> {
+	/// Wraps `store`, deriving the ciphers of values and keys from the 256-bit `key`.
+	pub fn new(store: S, key: &[u8; 32]) -> Self {
+		let cipher = |purpose: &[u8]| {
+			let subkey = Sha256::new()
+				.chain_update(b"iavl encrypted store ")
+				.chain_update(purpose)
+				.chain_update(key)
+				.finalize();
+
+			Aes256GcmSiv::new(&subkey)
+		};
+
+		let ciphers = Ciphers { values: cipher(b"values"), keys: cipher(b"keys") };
+
+		Self { store, ciphers: Arc::new(ciphers), clear_prefix_len: None }
+	}
+
+	/// Enables deterministic encryption of keys past their first `clear_prefix_len` bytes.
+	///
+	/// Equal keys encrypt equally, so lookups work as before, but stored keys are only ordered
+	/// by their clear prefixes. Ranges are thus narrowed by the clear prefixes of their bounds
+	/// and read a whole group of keys sharing a clear prefix at a time, to be filtered and sorted
+	/// once decrypted. Keeping 9 bytes in clear, i.e. the prefix byte and version leading the
+	/// keys of a [`MutableTree`](crate::MutableTree), keeps its version scans narrow.
+	pub fn with_key_encryption(mut self, clear_prefix_len: usize) -> Self {
+		self.clear_prefix_len = Some(clear_prefix_len);
+		self
+	}
+
+	pub fn inner(&self) -> &S {
+		&self.store
+	}
+
+	pub fn into_inner(self) -> S {
+		self.store
+	}
+
+	fn encrypt_key<K>(
+		&self,
+		key: &NonEmptyBz<K>,
+	) -> Result<NonEmptyBz<Vec<u8>>, EncryptedStoreError>
+	where
+		K: AsRef<[u8]>,
+	{
+		let key = key.get().as_ref();
+
+		let encrypted = match self.clear_prefix_len {
+			Some(clear_len) if key.len() > clear_len => {
+				let (clear, secret) = key.split_at(clear_len);
+
+				let encrypted = self
+					.ciphers
+					.keys
+					.encrypt(&Nonce::default(), Payload { msg: secret, aad: clear })
+					.map_err(|_| EncryptedStoreError::Encryption)?;
+
+				[clear, &encrypted].concat()
+			},
+			_ => key.to_vec(),
+		};
+
+		// unwrap is safe because the key itself is non-empty
+		Ok(NonEmptyBz::new(encrypted).unwrap())
+	}
+
+	fn decrypt_key(
+		&self,
+		key: NonEmptyBz<Bytes>,
+	) -> Result<NonEmptyBz<Bytes>, EncryptedStoreError> {
+		let clear_len = match self.clear_prefix_len {
+			Some(clear_len) if key.len().get() > clear_len => clear_len,
+			_ => return Ok(key),
+		};
+
+		let (clear, encrypted) = key.get().split_at(clear_len);
+
+		let secret = self
+			.ciphers
+			.keys
+			.decrypt(&Nonce::default(), Payload { msg: encrypted, aad: clear })
+			.map_err(|_| EncryptedStoreError::Decryption)?;
+
+		// unwrap is safe because the key has at least its clear prefix
+		Ok(NonEmptyBz::new([clear, &secret].concat().into()).unwrap())
+	}
+
+	fn encrypt_value(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>, EncryptedStoreError> {
+		let nonce = Aes256GcmSiv::generate_nonce(&mut OsRng);
+
+		let sealed = self
+			.ciphers
+			.values
+			.encrypt(&nonce, Payload { msg: value, aad: key })
+			.map_err(|_| EncryptedStoreError::Encryption)?;
+
+		Ok([nonce.as_slice(), &sealed].concat())
+	}
+
+	fn decrypt_value(
+		&self,
+		key: &[u8],
+		value: &[u8],
+	) -> Result<NonEmptyBz<Bytes>, EncryptedStoreError> {
+		if value.len() < NONCE_LEN {
+			return Err(EncryptedStoreError::Decryption);
+		}
+
+		let (nonce, sealed) = value.split_at(NONCE_LEN);
+
+		self.ciphers
+			.values
+			.decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: key })
+			.ok()
+			.and_then(|value| NonEmptyBz::new(value.into()))
+			.ok_or(EncryptedStoreError::Decryption)
+	}
+
+	/// Maps `bound` on keys to one on stored keys, keeping its clear prefix only if it is longer.
+	fn stored_bound(&self, bound: Bound<&NonEmptyBz<&[u8]>>, end: bool) -> Bound<Vec<u8>> {
+		let clear_len = self.clear_prefix_len.unwrap_or(usize::MAX);
+
+		match bound {
+			Bound::Included(key) if key.len().get() <= clear_len => {
+				Bound::Included(key.get().to_vec())
+			},
+			Bound::Excluded(key) if key.len().get() <= clear_len => {
+				Bound::Excluded(key.get().to_vec())
+			},
+			Bound::Included(key) | Bound::Excluded(key) => {
+				let clear = &key.get()[..clear_len];
+
+				if end {
+					prefix_end(clear)
+						.map_or(Bound::Unbounded, |end| Bound::Excluded(end.into_inner()))
+				} else if clear.is_empty() {
+					Bound::Unbounded
+				} else {
+					Bound::Included(clear.to_vec())
+				}
+			},
+			Bound::Unbounded => Bound::Unbounded,
+		}
+	}
+}
+
+impl<S> fmt::Debug for EncryptedStore<S>
+where
+	S: fmt::Debug,
+{
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("EncryptedStore")
+			.field("store", &self.store)
+			.field("clear_prefix_len", &self.clear_prefix_len)
+			.finish_non_exhaustive()
+	}
+}
+
+impl<S> KVStore for EncryptedStore<S>
+where
+	S: KVStore,
+{
+	type Error = EncryptedStoreError;
+
+	type Snapshot = EncryptedStore<S::Snapshot>;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(EncryptedStore {
+			store: self.store.snapshot().map_err(store_error)?,
+			ciphers: self.ciphers.clone(),
+			clear_prefix_len: self.clear_prefix_len,
+		})
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store
+			.get(self.encrypt_key(&key)?)
+			.map_err(store_error)?
+			.map(|value| self.decrypt_value(key.get().as_ref(), value.get()))
+			.transpose()
+	}
+
+	fn has<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.has(self.encrypt_key(&key)?).map_err(store_error)
+	}
+}
+
+impl<S> MutKVStore for EncryptedStore<S>
+where
+	S: MutKVStore,
+{
+	type Error = EncryptedStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let value = self.encrypt_value(key.get().as_ref(), value.get().as_ref())?;
+
+		// unwrap is safe because the sealed value has at least its nonce
+		self.store
+			.insert(self.encrypt_key(&key)?, NonEmptyBz::new(value).unwrap())
+			.map_err(store_error)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.remove(self.encrypt_key(&key)?).map_err(store_error)
+	}
+
+	/// Applies `batch` as a single batch of the wrapped store, thus as atomically as it does.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut encrypted = WriteBatch::new();
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => {
+					let value = self.encrypt_value(key.get(), value.get())?;
+
+					// unwrap is safe because the sealed value has at least its nonce
+					encrypted.insert(self.encrypt_key(&key)?, NonEmptyBz::new(value).unwrap());
+				},
+				WriteOp::Remove { key } => encrypted.remove(self.encrypt_key(&key)?),
+			}
+		}
+
+		self.store.write_batch(encrypted).map_err(store_error)
+	}
+}
+
+impl<S> KVIterator for EncryptedStore<S>
+where
+	S: KVIterator,
+{
+	type Error = EncryptedStoreError;
+
+	type FetchError = EncryptedStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		// the stored bounds only live for the call reading each chunk
+		Ok(ChunkedRange::new(range, move |plain_range, direction| {
+			// unwraps are safe because range bounds are made of non-empty keys
+			let (start, end) = (
+				plain_range.0.map(|key| NonEmptyBz::new(key).unwrap()),
+				plain_range.1.map(|key| NonEmptyBz::new(key).unwrap()),
+			);
+
+			let (start, end) = (
+				self.stored_bound(start.as_ref(), false),
+				self.stored_bound(end.as_ref(), true),
+			);
+
+			// unwraps are safe because bounds on stored keys are only kept if non-empty
+			let stored_range = (
+				start.as_ref().map(|key| NonEmptyBz::new(key.as_slice()).unwrap()),
+				end.as_ref().map(|key| NonEmptyBz::new(key.as_slice()).unwrap()),
+			);
+
+			let kvs = self.store.iter(stored_range).map_err(store_error)?;
+
+			let Some(clear_len) = self.clear_prefix_len else {
+				let decrypt = |kv: Result<_, _>| {
+					kv.map_err(store_error).and_then(|kv| self.decrypt_entry(kv))
+				};
+
+				let entries = match direction {
+					Direction::Forward => {
+						kvs.take(CHUNK_LEN).map(decrypt).collect::<Result<_, _>>()?
+					},
+					Direction::Reverse => {
+						kvs.rev().take(CHUNK_LEN).map(decrypt).collect::<Result<_, _>>()?
+					},
+				};
+
+				return Ok(Chunk::new(entries));
+			};
+
+			let stored = match direction {
+				Direction::Forward => read_clear_prefix_groups(kvs, clear_len),
+				Direction::Reverse => read_clear_prefix_groups(kvs.rev(), clear_len),
+			}
+			.map_err(store_error)?;
+
+			let Some((last_key, _)) = stored.last() else {
+				return Ok(None);
+			};
+
+			let resume = resume_past_group(last_key.get(), clear_len, direction);
+
+			let mut entries = stored
+				.into_iter()
+				.map(|kv| self.decrypt_entry(kv))
+				.filter(|kv| {
+					kv.as_ref().map_or(true, |(key, _)| {
+						RangeBounds::<[u8]>::contains(&plain_range, key.get().as_ref())
+					})
+				})
+				.collect::<Result<Vec<_>, _>>()?;
+
+			entries.sort_unstable_by(|(a, _), (b, _)| match direction {
+				Direction::Forward => a.cmp(b),
+				Direction::Reverse => b.cmp(a),
+			});
+
+			Ok(Some(Chunk::resuming(entries, resume)))
+		}))
+	}
+}
+
+impl<S> EncryptedStore<S> {
+	#[allow(clippy::type_complexity)]
+	fn decrypt_entry(
+		&self,
+		(key, value): (NonEmptyBz<Bytes>, NonEmptyBz<Bytes>),
+	) -> Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), EncryptedStoreError> {
+		let key = self.decrypt_key(key)?;
+		let value = self.decrypt_value(key.get(), value.get())?;
+
+		Ok((key, value))
+	}
+}
+
+/// Reads at least [`CHUNK_LEN`] entries off `kvs` if any, along with every entry sharing the
+/// clear prefix of the last one, as those are stored in no particular order.
+#[allow(clippy::type_complexity)]
+fn read_clear_prefix_groups<I, E>(
+	kvs: I,
+	clear_len: usize,
+) -> Result<Vec<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>)>, E>
+where
+	I: Iterator<Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), E>>,
+{
+	// keys no longer than their clear prefix are stored as is, hence make groups of their own
+	let group = |key: &NonEmptyBz<Bytes>| {
+		(key.len().get() > clear_len).then(|| key.get().slice(..clear_len))
+	};
+
+	let mut entries = Vec::new();
+
+	for kv in kvs {
+		let kv = kv?;
+
+		if let Some((last_key, _)) = entries.last()
+			&& entries.len() >= CHUNK_LEN
+			&& (group(last_key).is_none() || group(last_key) != group(&kv.0))
+		{
+			break;
+		}
+
+		entries.push(kv);
+	}
+
+	Ok(entries)
+}
+
+/// Returns the bound on keys resuming a range past `last_key` towards `direction`, skipping the
+/// whole group sharing its clear prefix, or [`None`] if no key is left past it.
+fn resume_past_group(
+	last_key: &Bytes,
+	clear_len: usize,
+	direction: Direction,
+) -> Option<Bound<Bytes>> {
+	if last_key.len() <= clear_len {
+		return Some(Bound::Excluded(last_key.clone()));
+	}
+
+	let clear = &last_key[..clear_len];
+
+	match direction {
+		Direction::Forward => prefix_end(clear).map(|end| Bound::Included(end.into_inner().into())),
+		// the clear prefix itself is a key stored as is, ahead of the group
+		Direction::Reverse => {
+			(!clear.is_empty()).then(|| Bound::Included(last_key.slice(..clear_len)))
+		},
+	}
+}
+
+fn store_error<E>(err: E) -> EncryptedStoreError
+where
+	E: core::error::Error + Send + Sync + 'static,
+{
+	EncryptedStoreError::Store(Box::new(err))
+}
@@ -0,0 +1,11 @@
+#[derive(Debug, thiserror::Error)]
+pub enum EncryptedStoreError {
+	#[error("store error: {0}")]
+	Store(Box<dyn core::error::Error + Send + Sync>),
+
+	#[error("encryption error: entry must not exceed the cipher's limits")]
+	Encryption,
+
+	#[error("decryption error: entry must be encrypted under the store's key")]
+	Decryption,
+}
@@ -0,0 +1,282 @@
+mod error;
+
+pub use self::error::FaultyStoreError;
+
+use core::ops::RangeBounds;
+
+use std::sync::{Arc, Mutex};
+
+use bytes::{Bytes, BytesMut};
+use nebz::NonEmptyBz;
+
+use super::{KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp};
+
+/// A store injecting faults into the calls to the wrapped store, to exercise error paths.
+///
+/// Faults are scheduled for the nth call of an [`Operation`] with [`FaultyStore::inject`].
+/// Clones and snapshots share the schedule and the call counts.
+#[derive(Debug, Clone)]
+pub struct FaultyStore<S> {
+	store: S,
+	state: Arc<Mutex<State>>,
+}
+
+/// Calls of a store that faults can be injected into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+	Get,
+	Has,
+	Insert,
+	Remove,
+	WriteBatch,
+	Iter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+	/// Fails the call with [`FaultyStoreError::Injected`] without reaching the wrapped store.
+	Fail,
+
+	/// Flips the lowest bit of the first byte of every value read or written by the call.
+	///
+	/// Removals go through unaffected.
+	Corrupt,
+
+	/// Applies the first half of the writes of a batch, or stores the first half of an inserted
+	/// value, then fails with [`FaultyStoreError::Injected`].
+	///
+	/// Other calls fail as with [`Fault::Fail`].
+	TornWrite,
+}
+
+#[derive(Debug, Default)]
+struct State {
+	calls: [usize; 6],
+	scheduled: Vec<(Operation, usize, Fault)>,
+}
+
+impl<S> FaultyStore<S> {
+	pub fn new(store: S) -> Self {
+		Self { store, state: Arc::default() }
+	}
+
+	/// Schedules `fault` for the `nth` call of `op` from now on, counting from zero.
+	pub fn inject(&self, op: Operation, nth: usize, fault: Fault) -> Result<(), FaultyStoreError> {
+		let mut state = self.state.lock()?;
+		let at = state.calls[op as usize] + nth;
+
+		state.scheduled.push((op, at, fault));
+
+		Ok(())
+	}
+
+	/// Drops the faults scheduled and not yet injected.
+	pub fn clear(&self) -> Result<(), FaultyStoreError> {
+		self.state.lock()?.scheduled.clear();
+
+		Ok(())
+	}
+
+	/// Returns how many times `op` was called.
+	pub fn calls(&self, op: Operation) -> Result<usize, FaultyStoreError> {
+		Ok(self.state.lock()?.calls[op as usize])
+	}
+
+	pub fn inner(&self) -> &S {
+		&self.store
+	}
+
+	pub fn into_inner(self) -> S {
+		self.store
+	}
+
+	/// Counts a call of `op`, returning the fault scheduled for it if any.
+	fn next_fault(&self, op: Operation) -> Result<Option<Fault>, FaultyStoreError> {
+		let mut state = self.state.lock()?;
+
+		let call = state.calls[op as usize];
+		state.calls[op as usize] += 1;
+
+		let scheduled = state.scheduled.iter().position(|&(o, at, _)| o == op && at == call);
+
+		Ok(scheduled.map(|i| state.scheduled.remove(i).2))
+	}
+}
+
+impl<S> KVStore for FaultyStore<S>
+where
+	S: KVStore,
+{
+	type Error = FaultyStoreError;
+
+	type Snapshot = FaultyStore<S::Snapshot>;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(FaultyStore {
+			store: self.store.snapshot().map_err(store_error)?,
+			state: self.state.clone(),
+		})
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let fault = self.next_fault(Operation::Get)?;
+		fail_unless_corrupting(Operation::Get, fault)?;
+
+		let value = self.store.get(key).map_err(store_error)?;
+
+		Ok(value.map(|value| {
+			if fault == Some(Fault::Corrupt) {
+				corrupt(value.get())
+			} else {
+				value
+			}
+		}))
+	}
+
+	fn has<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		fail_unless_corrupting(Operation::Has, self.next_fault(Operation::Has)?)?;
+
+		self.store.has(key).map_err(store_error)
+	}
+}
+
+impl<S> MutKVStore for FaultyStore<S>
+where
+	S: MutKVStore,
+{
+	type Error = FaultyStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let value = value.as_ref_slice();
+
+		match self.next_fault(Operation::Insert)? {
+			Some(Fault::Fail) => Err(FaultyStoreError::Injected(Operation::Insert)),
+			Some(Fault::Corrupt) => {
+				self.store.insert(key, corrupt(value.get())).map_err(store_error)
+			},
+			Some(Fault::TornWrite) => {
+				let torn = &value.get()[..value.len().get().div_ceil(2)];
+
+				// unwrap is safe because the first half of a non-empty value is non-empty
+				self.store.insert(key, NonEmptyBz::new(torn).unwrap()).map_err(store_error)?;
+
+				Err(FaultyStoreError::Injected(Operation::Insert))
+			},
+			None => self.store.insert(key, value).map_err(store_error),
+		}
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		fail_unless_corrupting(Operation::Remove, self.next_fault(Operation::Remove)?)?;
+
+		self.store.remove(key).map_err(store_error)
+	}
+
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		match self.next_fault(Operation::WriteBatch)? {
+			Some(Fault::Fail) => Err(FaultyStoreError::Injected(Operation::WriteBatch)),
+			Some(Fault::Corrupt) => {
+				let mut corrupted = WriteBatch::new();
+
+				for op in batch {
+					match op {
+						WriteOp::Insert { key, value } => {
+							corrupted.insert(key, corrupt(value.get()))
+						},
+						WriteOp::Remove { key } => corrupted.remove(key),
+					}
+				}
+
+				self.store.write_batch(corrupted).map_err(store_error)
+			},
+			Some(Fault::TornWrite) => {
+				let mut torn = WriteBatch::new();
+
+				for op in batch.ops().iter().take(batch.len() / 2) {
+					match op {
+						WriteOp::Insert { key, value } => torn.insert(key.clone(), value.clone()),
+						WriteOp::Remove { key } => torn.remove(key.clone()),
+					}
+				}
+
+				self.store.write_batch(torn).map_err(store_error)?;
+
+				Err(FaultyStoreError::Injected(Operation::WriteBatch))
+			},
+			None => self.store.write_batch(batch).map_err(store_error),
+		}
+	}
+}
+
+impl<S> KVIterator for FaultyStore<S>
+where
+	S: KVIterator,
+{
+	type Error = FaultyStoreError;
+
+	type FetchError = FaultyStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let fault = self.next_fault(Operation::Iter)?;
+		fail_unless_corrupting(Operation::Iter, fault)?;
+
+		let kvs = self.store.iter(range).map_err(store_error)?.map(move |kv| {
+			let (key, value) = kv.map_err(store_error)?;
+
+			if fault == Some(Fault::Corrupt) {
+				Ok((key, corrupt(value.get())))
+			} else {
+				Ok((key, value))
+			}
+		});
+
+		Ok(kvs)
+	}
+}
+
+/// Fails with [`FaultyStoreError::Injected`] if `fault` is set to anything but corrupting `op`.
+fn fail_unless_corrupting(op: Operation, fault: Option<Fault>) -> Result<(), FaultyStoreError> {
+	match fault {
+		None | Some(Fault::Corrupt) => Ok(()),
+		Some(Fault::Fail | Fault::TornWrite) => Err(FaultyStoreError::Injected(op)),
+	}
+}
+
+fn corrupt(value: &[u8]) -> NonEmptyBz<Bytes> {
+	let mut corrupted = BytesMut::from(value);
+	corrupted[0] ^= 1;
+
+	// unwrap is safe because values are non-empty
+	NonEmptyBz::new(corrupted.freeze()).unwrap()
+}
+
+fn store_error<E>(err: E) -> FaultyStoreError
+where
+	E: core::error::Error + Send + Sync + 'static,
+{
+	FaultyStoreError::Store(Box::new(err))
+}
@@ -0,0 +1,21 @@
+use std::sync::PoisonError;
+
+use super::Operation;
+
+#[derive(Debug, thiserror::Error)]
+pub enum FaultyStoreError {
+	#[error("store error: {0}")]
+	Store(Box<dyn core::error::Error + Send + Sync>),
+
+	#[error("injected fault error: {0:?} call failed as scheduled")]
+	Injected(Operation),
+
+	#[error("poisoned lock error: lock must not be poisoned")]
+	PoisonedLock,
+}
+
+impl<T> From<PoisonError<T>> for FaultyStoreError {
+	fn from(_err: PoisonError<T>) -> Self {
+		Self::PoisonedLock
+	}
+}
@@ -0,0 +1,301 @@
+mod error;
+
+pub use self::error::LmdbStoreError;
+
+use core::ops::{Bound, RangeBounds};
+
+use std::{
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+use bon::Builder;
+use bytes::Bytes;
+use heed::{Database, Env, EnvOpenOptions, RoTxn, WithoutTls};
+use nebz::NonEmptyBz;
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+};
+
+type RawDatabase = Database<heed::types::Bytes, heed::types::Bytes>;
+
+type SharedRoTxn = Arc<Mutex<RoTxn<'static, WithoutTls>>>;
+
+/// A store backed by an LMDB database through [`heed`].
+///
+/// Reads on the store itself copy values out of LMDB, whereas point reads on its
+/// [snapshots](LmdbSnapshot) do not. Tree lookups read through a snapshot taken per query, and
+/// copy the nodes they decode, so no borrowed value outlives a query.
+#[derive(Clone)]
+pub struct LmdbStore {
+	env: Env<WithoutTls>,
+	db: RawDatabase,
+}
+
+/// Options of the LMDB environment opened by [`LmdbStore::open`].
+#[derive(Debug, Clone, Builder)]
+pub struct LmdbOptions {
+	/// Maximum size of the memory map, i.e. of the database, in bytes.
+	#[builder(default = LmdbOptions::DEFAULT_MAP_SIZE)]
+	map_size: usize,
+
+	/// Maximum number of read transactions open at once, snapshots included.
+	#[builder(default = LmdbOptions::DEFAULT_MAX_READERS)]
+	max_readers: u32,
+}
+
+/// Read-only view of an [`LmdbStore`] holding a single long-lived read transaction, see
+/// [`KVStore::snapshot`].
+///
+/// Values got by [`KVStore::get`] are read straight from LMDB's memory map, so each of them keeps
+/// the transaction open, and thus one of [`LmdbOptions::max_readers`] slots taken and the pages
+/// it reads from unreclaimed, until dropped. They are meant to be short-lived, values to be kept
+/// should be copied. Ranges are copied out of LMDB as they are iterated.
+#[derive(Clone)]
+pub struct LmdbSnapshot {
+	txn: SharedRoTxn,
+	db: RawDatabase,
+}
+
+/// Value within the memory map of the read transaction it keeps open.
+struct TxnValue {
+	_txn: SharedRoTxn,
+	value: &'static [u8],
+}
+
+impl LmdbOptions {
+	const DEFAULT_MAP_SIZE: usize = 1 << 30;
+
+	const DEFAULT_MAX_READERS: u32 = 126;
+}
+
+impl LmdbStore {
+	pub fn new(env: Env<WithoutTls>, db_name: &str) -> Result<Self, LmdbStoreError> {
+		let mut write_tx = env.write_txn()?;
+		let db = env.create_database(&mut write_tx, Some(db_name))?;
+		write_tx.commit()?;
+
+		Ok(Self { env, db })
+	}
+
+	/// Opens the LMDB environment in directory `path` along with its database `db_name`,
+	/// creating the latter if missing.
+	///
+	/// # Safety
+	///
+	/// The environment must not already be open within the process, nor be modified by others
+	/// in ways LMDB does not expect, see [`EnvOpenOptions::open`].
+	pub unsafe fn open<P>(
+		path: P,
+		db_name: &str,
+		options: LmdbOptions,
+	) -> Result<Self, LmdbStoreError>
+	where
+		P: AsRef<Path>,
+	{
+		let mut env_options = EnvOpenOptions::new().read_txn_without_tls();
+		env_options.map_size(options.map_size).max_readers(options.max_readers).max_dbs(1);
+
+		// SAFETY: upheld by the caller
+		let env = unsafe { env_options.open(path)? };
+
+		Self::new(env, db_name)
+	}
+}
+
+impl KVStore for LmdbStore {
+	type Error = LmdbStoreError;
+
+	type Snapshot = LmdbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		let txn = self.env.clone().static_read_txn()?;
+
+		Ok(LmdbSnapshot { txn: Arc::new(Mutex::new(txn)), db: self.db })
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let read_tx = self.env.read_txn()?;
+
+		self.db
+			.get(&read_tx, key.get().as_ref())?
+			.map(|value| make_nebz_bytes(Bytes::copy_from_slice(value)))
+			.transpose()
+	}
+}
+
+impl MutKVStore for LmdbStore {
+	type Error = LmdbStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let mut write_tx = self.env.write_txn()?;
+
+		let updated = self.db.get(&write_tx, key.get().as_ref())?.is_some();
+		self.db.put(&mut write_tx, key.get().as_ref(), value.get().as_ref())?;
+
+		write_tx.commit()?;
+
+		Ok(updated)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let mut write_tx = self.env.write_txn()?;
+
+		let removed = self.db.delete(&mut write_tx, key.get().as_ref())?;
+
+		write_tx.commit()?;
+
+		Ok(removed)
+	}
+
+	/// Applies `batch` within a single write transaction.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut write_tx = self.env.write_txn()?;
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => {
+					self.db.put(&mut write_tx, key.get(), value.get())?
+				},
+				WriteOp::Remove { key } => {
+					self.db.delete(&mut write_tx, key.get())?;
+				},
+			}
+		}
+
+		write_tx.commit()?;
+
+		Ok(())
+	}
+}
+
+impl KVIterator for LmdbStore {
+	type Error = LmdbStoreError;
+
+	type FetchError = LmdbStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		// each chunk is read within a read transaction of its own, ended before it is yielded
+		Ok(ChunkedRange::new(range, |range, direction| {
+			let read_tx = self.env.read_txn()?;
+
+			read_chunk(self.db, &read_tx, range, direction)
+		}))
+	}
+}
+
+impl KVStore for LmdbSnapshot {
+	type Error = LmdbStoreError;
+
+	type Snapshot = LmdbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(self.clone())
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let read_tx = self.txn.lock()?;
+
+		self.db
+			.get(&read_tx, key.get().as_ref())?
+			.map(|value| make_nebz_bytes(self.borrow_value(value)))
+			.transpose()
+	}
+}
+
+impl KVIterator for LmdbSnapshot {
+	type Error = LmdbStoreError;
+
+	type FetchError = LmdbStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		// entries are copied out, so that none outlives the call by keeping the transaction open
+		Ok(ChunkedRange::new(range, |range, direction| {
+			let read_tx = self.txn.lock()?;
+
+			read_chunk(self.db, &read_tx, range, direction)
+		}))
+	}
+}
+
+impl LmdbSnapshot {
+	/// Wraps `value`, read through the transaction of `self`, into [`Bytes`] without copying it.
+	fn borrow_value(&self, value: &[u8]) -> Bytes {
+		// SAFETY: LMDB keeps the data read through a transaction in place until the transaction
+		// ends, which the returned value prevents by holding it
+		let value = unsafe { &*(value as *const [u8]) };
+
+		Bytes::from_owner(TxnValue { _txn: self.txn.clone(), value })
+	}
+}
+
+impl AsRef<[u8]> for TxnValue {
+	fn as_ref(&self) -> &[u8] {
+		self.value
+	}
+}
+
+/// Reads the next chunk of `range` towards `direction` within `read_tx`, copying it out.
+fn read_chunk(
+	db: RawDatabase,
+	read_tx: &RoTxn<'_, WithoutTls>,
+	range: (Bound<&[u8]>, Bound<&[u8]>),
+	direction: Direction,
+) -> Result<Option<Chunk>, LmdbStoreError> {
+	let copy = |kv: heed::Result<(&[u8], &[u8])>| {
+		let (key, value) = kv?;
+
+		Ok((
+			make_nebz_bytes(Bytes::copy_from_slice(key))?,
+			make_nebz_bytes(Bytes::copy_from_slice(value))?,
+		))
+	};
+
+	let entries: Result<_, LmdbStoreError> = match direction {
+		Direction::Forward => db.range(read_tx, &range)?.take(CHUNK_LEN).map(copy).collect(),
+		Direction::Reverse => db.rev_range(read_tx, &range)?.take(CHUNK_LEN).map(copy).collect(),
+	};
+
+	entries.map(Chunk::new)
+}
+
+fn make_nebz_bytes(bz: Bytes) -> Result<NonEmptyBz<Bytes>, LmdbStoreError> {
+	NonEmptyBz::new(bz).ok_or(LmdbStoreError::EmptyValue)
+}
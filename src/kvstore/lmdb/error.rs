@@ -0,0 +1,19 @@
+use std::sync::PoisonError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LmdbStoreError {
+	#[error("database error: {0}")]
+	Database(#[from] heed::Error),
+
+	#[error("poisoned lock error: lock must not be poisoned")]
+	PoisonedLock,
+
+	#[error("empty value error: value must not be empty")]
+	EmptyValue,
+}
+
+impl<T> From<PoisonError<T>> for LmdbStoreError {
+	fn from(_err: PoisonError<T>) -> Self {
+		Self::PoisonedLock
+	}
+}
@@ -0,0 +1,140 @@
+mod error;
+
+pub use self::error::MemStoreError;
+
+use core::ops::RangeBounds;
+
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, RwLock},
+};
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+};
+
+type Map = BTreeMap<Bytes, NonEmptyBz<Bytes>>;
+
+/// An in-memory store backed by a [`BTreeMap`].
+///
+/// Clones share the same underlying map, whereas [`MemStore::snapshot`] detaches a point-in-time
+/// copy of it.
+#[derive(Debug, Clone, Default)]
+pub struct MemStore {
+	map: Arc<RwLock<Arc<Map>>>,
+}
+
+impl MemStore {
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Returns a store holding the current contents of `self`.
+	///
+	/// Taking a snapshot is cheap as the contents are shared until either store is written to.
+	/// The first write to `self` while the snapshot is alive then copies the whole map, in time
+	/// and memory linear in the number of entries, though keys and values are reference-counted
+	/// rather than copied. Snapshots are thus best dropped before writing.
+	pub fn snapshot(&self) -> Result<Self, MemStoreError> {
+		let map = self.map.read()?.clone();
+
+		Ok(Self { map: Arc::new(RwLock::new(map)) })
+	}
+}
+
+impl KVStore for MemStore {
+	type Error = MemStoreError;
+
+	type Snapshot = MemStore;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		MemStore::snapshot(self)
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		Ok(self.map.read()?.get(key.get().as_ref()).cloned())
+	}
+}
+
+impl MutKVStore for MemStore {
+	type Error = MemStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let key = Bytes::copy_from_slice(key.get().as_ref());
+		let value = value.as_ref_slice().into();
+
+		Ok(Arc::make_mut(&mut *self.map.write()?).insert(key, value).is_some())
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		Ok(Arc::make_mut(&mut *self.map.write()?).remove(key.get().as_ref()).is_some())
+	}
+
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut guard = self.map.write()?;
+		let map = Arc::make_mut(&mut *guard);
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => map.insert(key.into_inner(), value),
+				WriteOp::Remove { key } => map.remove(key.get()),
+			};
+		}
+
+		Ok(())
+	}
+}
+
+impl KVIterator for MemStore {
+	type Error = MemStoreError;
+
+	type FetchError = MemStoreError;
+
+	/// Iterates over the contents as of now, which, as with [`MemStore::snapshot`], makes the
+	/// first write while the iterator is alive copy the whole map.
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let map = self.map.read()?.clone();
+
+		// read lazily off the contents as of now, which writers leave in place
+		Ok(ChunkedRange::new(range, move |range, direction| {
+			let kvs = map.range::<[u8], _>(range);
+
+			// unwrap is safe because keys are inserted non-empty
+			let entry = |(key, value): (&Bytes, &NonEmptyBz<Bytes>)| {
+				(NonEmptyBz::new(key.clone()).unwrap(), value.clone())
+			};
+
+			let entries = match direction {
+				Direction::Forward => kvs.take(CHUNK_LEN).map(entry).collect(),
+				Direction::Reverse => kvs.rev().take(CHUNK_LEN).map(entry).collect(),
+			};
+
+			Ok(Chunk::new(entries))
+		}))
+	}
+}
@@ -0,0 +1,13 @@
+use std::sync::PoisonError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MemStoreError {
+	#[error("poisoned lock error: lock must not be poisoned")]
+	PoisonedLock,
+}
+
+impl<T> From<PoisonError<T>> for MemStoreError {
+	fn from(_err: PoisonError<T>) -> Self {
+		Self::PoisonedLock
+	}
+}
@@ -0,0 +1,197 @@
+use core::ops::{Bound, RangeBounds};
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+};
+
+/// A store confining its keys to those of the wrapped store starting with a given prefix.
+///
+/// Keys are prefixed on the way in and stripped on the way out, so several trees can share one
+/// database as long as none of their prefixes is a prefix of another.
+#[derive(Debug, Clone)]
+pub struct PrefixStore<S> {
+	store: S,
+	prefix: Bytes,
+}
+
+impl<S> PrefixStore<S> {
+	pub fn new<P>(store: S, prefix: P) -> Self
+	where
+		P: Into<Bytes>,
+	{
+		Self { store, prefix: prefix.into() }
+	}
+
+	pub fn prefix(&self) -> &[u8] {
+		&self.prefix
+	}
+
+	pub fn inner(&self) -> &S {
+		&self.store
+	}
+
+	pub fn into_inner(self) -> S {
+		self.store
+	}
+
+	fn prefixed<K>(&self, key: &NonEmptyBz<K>) -> NonEmptyBz<Vec<u8>>
+	where
+		K: AsRef<[u8]>,
+	{
+		let key = [self.prefix.as_ref(), key.get().as_ref()].concat();
+
+		// unwrap is safe because the key itself is non-empty
+		NonEmptyBz::new(key).unwrap()
+	}
+
+	fn prefixed_raw(&self, key: &[u8]) -> NonEmptyBz<Vec<u8>> {
+		// unwrap is safe because range bounds are made of non-empty keys
+		self.prefixed(&NonEmptyBz::new(key).unwrap())
+	}
+}
+
+impl<S> KVStore for PrefixStore<S>
+where
+	S: KVStore,
+{
+	type Error = S::Error;
+
+	type Snapshot = PrefixStore<S::Snapshot>;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(PrefixStore { store: self.store.snapshot()?, prefix: self.prefix.clone() })
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.get(self.prefixed(&key))
+	}
+
+	fn has<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.has(self.prefixed(&key))
+	}
+}
+
+impl<S> MutKVStore for PrefixStore<S>
+where
+	S: MutKVStore,
+{
+	type Error = S::Error;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		self.store.insert(self.prefixed(&key), value)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.remove(self.prefixed(&key))
+	}
+
+	/// Applies `batch` as a single batch of the wrapped store, thus as atomically as it does.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut prefixed = WriteBatch::new();
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => prefixed.insert(self.prefixed(&key), value),
+				WriteOp::Remove { key } => prefixed.remove(self.prefixed(&key)),
+			}
+		}
+
+		self.store.write_batch(prefixed)
+	}
+}
+
+impl<S> KVIterator for PrefixStore<S>
+where
+	S: KVIterator,
+	S::FetchError: Into<<S as KVIterator>::Error>,
+{
+	type Error = <S as KVIterator>::Error;
+
+	/// Errors of the wrapped store's calls, as `range` is read a chunk per call.
+	type FetchError = <S as KVIterator>::Error;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		// the prefixed bounds only live for the call reading each chunk
+		Ok(ChunkedRange::new(range, |(start, end), direction| {
+			let start = match start {
+				Bound::Included(key) => Bound::Included(self.prefixed_raw(key)),
+				Bound::Excluded(key) => Bound::Excluded(self.prefixed_raw(key)),
+				// the prefix itself is excluded, since stripping it off would leave an empty key
+				Bound::Unbounded => {
+					NonEmptyBz::new(self.prefix.to_vec()).map_or(Bound::Unbounded, Bound::Excluded)
+				},
+			};
+
+			let end = match end {
+				Bound::Included(key) => Bound::Included(self.prefixed_raw(key)),
+				Bound::Excluded(key) => Bound::Excluded(self.prefixed_raw(key)),
+				Bound::Unbounded => {
+					prefix_end(&self.prefix).map_or(Bound::Unbounded, Bound::Excluded)
+				},
+			};
+
+			let range = (
+				start.as_ref().map(NonEmptyBz::as_ref_slice),
+				end.as_ref().map(NonEmptyBz::as_ref_slice),
+			);
+
+			let kvs = self.store.iter(range)?;
+
+			let strip = |kv: Result<_, S::FetchError>| {
+				let (key, value): (NonEmptyBz<Bytes>, _) = kv.map_err(Into::into)?;
+
+				// unwrap is safe because the bounds only admit keys longer than the prefix
+				let key = NonEmptyBz::new(key.into_inner().slice(self.prefix.len()..)).unwrap();
+
+				Ok((key, value))
+			};
+
+			let entries = match direction {
+				Direction::Forward => kvs.take(CHUNK_LEN).map(strip).collect::<Result<_, _>>()?,
+				Direction::Reverse => {
+					kvs.rev().take(CHUNK_LEN).map(strip).collect::<Result<_, _>>()?
+				},
+			};
+
+			Ok(Chunk::new(entries))
+		}))
+	}
+}
+
+/// Returns the least key greater than all keys starting with `prefix`, if any.
+pub(super) fn prefix_end(prefix: &[u8]) -> Option<NonEmptyBz<Vec<u8>>> {
+	let last = prefix.iter().rposition(|&b| b != u8::MAX)?;
+
+	let mut end = prefix[..=last].to_vec();
+	end[last] += 1;
+
+	NonEmptyBz::new(end)
+}
@@ -8,14 +8,23 @@ use std::sync::Arc;
 
 use bytes::Bytes;
 use nebz::NonEmptyBz;
-use redb::{Database, TableDefinition, TypeName};
+use redb::{Database, Durability, ReadOnlyTable, TableDefinition, TypeName, WriteTransaction};
 
-use super::{KVIterator, KVStore, MutKVStore};
+use super::{KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp};
 
 #[derive(Clone)]
 pub struct RedbStore {
 	db: Arc<Database>,
 	table: TableDefinition<'static, Key, Value>,
+	durability: Durability,
+	two_phase_commit: bool,
+}
+
+/// Read-only view of a [`RedbStore`] holding a single read transaction, see
+/// [`KVStore::snapshot`].
+#[derive(Debug, Clone)]
+pub struct RedbSnapshot {
+	table: Arc<ReadOnlyTable<Key, Value>>,
 }
 
 #[derive(Debug)]
@@ -25,20 +34,74 @@ struct Key;
 struct Value;
 
 impl RedbStore {
+	/// Opens `table_name` of `db` with the default commit options, see [`RedbStore::builder`].
 	pub fn new(db: Arc<Database>, table_name: &'static str) -> Result<Self, RedbStoreError> {
-		let table = TableDefinition::new(table_name);
+		Self::builder().db(db).table_name(table_name).build()
+	}
+
+	/// Compacts the database, returning whether it shrank.
+	///
+	/// Compaction needs exclusive access to the database, thus fails with
+	/// [`RedbStoreError::SharedDatabase`] while clones of the store, e.g. held by trees or their
+	/// snapshots, or any other reference to the database or any transaction is alive. A tree
+	/// hands its store back through [`MutableTree::into_db`], to be reopened through
+	/// [`MutableTree::load_latest_version`] once compacted.
+	///
+	/// [`MutableTree::into_db`]: crate::MutableTree::into_db
+	/// [`MutableTree::load_latest_version`]: crate::MutableTree::load_latest_version
+	pub fn compact(&mut self) -> Result<bool, RedbStoreError> {
+		Arc::get_mut(&mut self.db)
+			.ok_or(RedbStoreError::SharedDatabase)?
+			.compact()
+			.map_err(From::from)
+	}
+
+	fn begin_write(&self) -> Result<WriteTransaction, RedbStoreError> {
+		let mut write_tx = self.db.begin_write()?;
 
-		let write_tx = db.begin_write()?;
-		write_tx.open_table(table)?;
+		write_tx.set_durability(self.durability);
+		write_tx.set_two_phase_commit(self.two_phase_commit);
+
+		Ok(write_tx)
+	}
+}
+
+#[bon::bon]
+impl RedbStore {
+	/// Opens `table_name` of `db`, creating it if missing.
+	///
+	/// Every write transaction commits with `durability`, [`Durability::Immediate`] by default,
+	/// e.g. [`Durability::Eventual`] trades the latest commits on a crash for throughput. Enabling
+	/// `two_phase_commit` guards commits against torn page writes at the cost of another fsync.
+	#[builder(start_fn = builder, finish_fn = build, builder_type = RedbStoreBuilder)]
+	pub fn with_options(
+		db: Arc<Database>,
+		table_name: &'static str,
+		#[builder(default = Durability::Immediate)] durability: Durability,
+		#[builder(default)] two_phase_commit: bool,
+	) -> Result<Self, RedbStoreError> {
+		let store =
+			Self { db, table: TableDefinition::new(table_name), durability, two_phase_commit };
+
+		let write_tx = store.begin_write()?;
+		write_tx.open_table(store.table)?;
 		write_tx.commit()?;
 
-		Ok(Self { db, table })
+		Ok(store)
 	}
 }
 
 impl KVStore for RedbStore {
 	type Error = RedbStoreError;
 
+	type Snapshot = RedbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		let table = self.db.begin_read()?.open_table(self.table)?;
+
+		Ok(RedbSnapshot { table: Arc::new(table) })
+	}
+
 	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
 	where
 		K: AsRef<[u8]>,
@@ -62,7 +125,7 @@ impl MutKVStore for RedbStore {
 		K: AsRef<[u8]>,
 		V: AsRef<[u8]>,
 	{
-		let write_tx = self.db.begin_write()?;
+		let write_tx = self.begin_write()?;
 
 		let updated = write_tx
 			.open_table(self.table)?
@@ -78,7 +141,7 @@ impl MutKVStore for RedbStore {
 	where
 		K: AsRef<[u8]>,
 	{
-		let write_tx = self.db.begin_write()?;
+		let write_tx = self.begin_write()?;
 
 		let removed = write_tx.open_table(self.table)?.remove(key.as_ref_slice())?.is_some();
 
@@ -86,6 +149,28 @@ impl MutKVStore for RedbStore {
 
 		Ok(removed)
 	}
+
+	/// Applies `batch` within a single write transaction.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let write_tx = self.begin_write()?;
+
+		{
+			let mut table = write_tx.open_table(self.table)?;
+
+			for op in batch {
+				match op {
+					WriteOp::Insert { key, value } => {
+						table.insert(key.as_ref_slice(), value.as_ref_slice())?
+					},
+					WriteOp::Remove { key } => table.remove(key.as_ref_slice())?,
+				};
+			}
+		}
+
+		write_tx.commit()?;
+
+		Ok(())
+	}
 }
 
 impl KVIterator for RedbStore {
@@ -117,6 +202,50 @@ impl KVIterator for RedbStore {
 	}
 }
 
+impl KVStore for RedbSnapshot {
+	type Error = RedbStoreError;
+
+	type Snapshot = RedbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(self.clone())
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		Ok(self.table.get(key.as_ref_slice())?.map(|v| v.value().into()))
+	}
+}
+
+impl KVIterator for RedbSnapshot {
+	type Error = RedbStoreError;
+
+	type FetchError = RedbStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let iter = self
+			.table
+			.range(range)?
+			.map(|kv| kv.map(|(k, v)| (k.value().into(), v.value().into())))
+			.map(|kv| kv.map_err(From::from));
+
+		Ok(iter)
+	}
+}
+
 impl redb::Key for Key {
 	fn compare(data1: &[u8], data2: &[u8]) -> Ordering {
 		data1.cmp(data2)
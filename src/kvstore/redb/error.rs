@@ -15,6 +15,12 @@ pub enum RedbStoreError {
 	#[error("storage error: {0}")]
 	Storage(#[from] redb::StorageError),
 
+	#[error("compaction error: {0}")]
+	Compaction(#[from] redb::CompactionError),
+
+	#[error("shared database error: trees, snapshots and store clones must be dropped to compact")]
+	SharedDatabase,
+
 	#[error("empty value error: value must not be empty")]
 	EmptyValue,
 }
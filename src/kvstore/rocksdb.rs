@@ -0,0 +1,310 @@
+mod error;
+
+pub use self::error::RocksDbStoreError;
+
+use core::{
+	mem,
+	ops::{Bound, RangeBounds},
+};
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+use rocksdb::{
+	ColumnFamily, DB, DBAccess, DBIteratorWithThreadMode, IteratorMode, ReadOptions,
+	SnapshotWithThreadMode,
+};
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+};
+
+/// A store backed by a column family of a RocksDB database.
+///
+/// Nodes and roots are kept under the same `s<version><nonce>` keys as Go IAVL v1. Databases
+/// written by Go IAVL are not guaranteed to load as is though, e.g. its empty roots are stored as
+/// empty values, which stores reject.
+#[derive(Clone)]
+pub struct RocksDbStore {
+	db: Arc<DB>,
+	cf_name: Option<Arc<str>>,
+}
+
+/// Read-only view of a [`RocksDbStore`] over a RocksDB snapshot, see [`KVStore::snapshot`].
+#[derive(Clone)]
+pub struct RocksDbSnapshot {
+	inner: Arc<SnapshotInner>,
+	cf_name: Option<Arc<str>>,
+}
+
+struct SnapshotInner {
+	// declared ahead of `db` so as to be released first
+	snapshot: SnapshotWithThreadMode<'static, DB>,
+	db: Arc<DB>,
+}
+
+impl RocksDbStore {
+	/// Creates a store over the default column family of `db`.
+	pub fn new(db: Arc<DB>) -> Self {
+		Self { db, cf_name: None }
+	}
+
+	/// Creates a store over column family `cf_name` of `db`, which must be opened along with it.
+	pub fn with_column_family(db: Arc<DB>, cf_name: &str) -> Result<Self, RocksDbStoreError> {
+		column_family(&db, Some(cf_name))?;
+
+		Ok(Self { db, cf_name: Some(cf_name.into()) })
+	}
+
+	fn cf(&self) -> Result<Option<&ColumnFamily>, RocksDbStoreError> {
+		column_family(&self.db, self.cf_name.as_deref())
+	}
+}
+
+impl KVStore for RocksDbStore {
+	type Error = RocksDbStoreError;
+
+	type Snapshot = RocksDbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		// SAFETY: the snapshot borrows the database behind `db`, which is kept alive, and in
+		// place, by the `Arc` stored along with it and released after it
+		let snapshot = unsafe {
+			mem::transmute::<SnapshotWithThreadMode<'_, DB>, SnapshotWithThreadMode<'static, DB>>(
+				self.db.snapshot(),
+			)
+		};
+
+		let inner = SnapshotInner { snapshot, db: self.db.clone() };
+
+		Ok(RocksDbSnapshot { inner: Arc::new(inner), cf_name: self.cf_name.clone() })
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let value = match self.cf()? {
+			Some(cf) => self.db.get_cf(cf, key.get())?,
+			None => self.db.get(key.get())?,
+		};
+
+		value.map(|value| make_nebz_bytes(value.into())).transpose()
+	}
+}
+
+impl MutKVStore for RocksDbStore {
+	type Error = RocksDbStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let updated = self.has(key.as_ref_slice())?;
+
+		match self.cf()? {
+			Some(cf) => self.db.put_cf(cf, key.get(), value.get())?,
+			None => self.db.put(key.get(), value.get())?,
+		}
+
+		Ok(updated)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let removed = self.has(key.as_ref_slice())?;
+
+		match self.cf()? {
+			Some(cf) => self.db.delete_cf(cf, key.get())?,
+			None => self.db.delete(key.get())?,
+		}
+
+		Ok(removed)
+	}
+
+	/// Applies `batch` atomically as a single RocksDB write batch.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let cf = self.cf()?;
+		let mut rocksdb_batch = rocksdb::WriteBatch::default();
+
+		for op in batch {
+			match (op, cf) {
+				(WriteOp::Insert { key, value }, Some(cf)) => {
+					rocksdb_batch.put_cf(cf, key.get(), value.get())
+				},
+				(WriteOp::Insert { key, value }, None) => rocksdb_batch.put(key.get(), value.get()),
+				(WriteOp::Remove { key }, Some(cf)) => rocksdb_batch.delete_cf(cf, key.get()),
+				(WriteOp::Remove { key }, None) => rocksdb_batch.delete(key.get()),
+			}
+		}
+
+		self.db.write(rocksdb_batch).map_err(From::from)
+	}
+}
+
+impl KVIterator for RocksDbStore {
+	type Error = RocksDbStoreError;
+
+	type FetchError = RocksDbStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let cf = self.cf()?;
+
+		// each chunk is read by an iterator of its own, so chunks may see writes made in between
+		Ok(ChunkedRange::new(range, move |range, direction| {
+			read_chunk(range, direction, |read_options, mode| match cf {
+				Some(cf) => self.db.iterator_cf_opt(cf, read_options, mode),
+				None => self.db.iterator_opt(mode, read_options),
+			})
+		}))
+	}
+}
+
+impl KVStore for RocksDbSnapshot {
+	type Error = RocksDbStoreError;
+
+	type Snapshot = RocksDbSnapshot;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(self.clone())
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let SnapshotInner { snapshot, db } = &*self.inner;
+
+		let value = match column_family(db, self.cf_name.as_deref())? {
+			Some(cf) => snapshot.get_cf(cf, key.get())?,
+			None => snapshot.get(key.get())?,
+		};
+
+		value.map(|value| make_nebz_bytes(value.into())).transpose()
+	}
+}
+
+impl KVIterator for RocksDbSnapshot {
+	type Error = RocksDbStoreError;
+
+	type FetchError = RocksDbStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let SnapshotInner { snapshot, db } = &*self.inner;
+		let cf = column_family(db, self.cf_name.as_deref())?;
+
+		// each chunk is read by an iterator of its own over the snapshot
+		Ok(ChunkedRange::new(range, move |range, direction| {
+			read_chunk(range, direction, |read_options, mode| match cf {
+				Some(cf) => snapshot.iterator_cf_opt(cf, read_options, mode),
+				None => snapshot.iterator_opt(mode, read_options),
+			})
+		}))
+	}
+}
+
+fn column_family<'db>(
+	db: &'db DB,
+	cf_name: Option<&str>,
+) -> Result<Option<&'db ColumnFamily>, RocksDbStoreError> {
+	cf_name
+		.map(|name| {
+			db.cf_handle(name).ok_or_else(|| RocksDbStoreError::MissingColumnFamily(name.into()))
+		})
+		.transpose()
+}
+
+/// Reads the chunk of `range` towards `direction` off the iterator opened by `iterate`.
+fn read_chunk<'db, D>(
+	range: (Bound<&[u8]>, Bound<&[u8]>),
+	direction: Direction,
+	iterate: impl FnOnce(ReadOptions, IteratorMode<'_>) -> DBIteratorWithThreadMode<'db, D>,
+) -> Result<Option<Chunk>, RocksDbStoreError>
+where
+	D: DBAccess,
+{
+	let Some(read_options) = make_read_options(range) else {
+		return Ok(None);
+	};
+
+	let mode = match direction {
+		Direction::Forward => IteratorMode::Start,
+		Direction::Reverse => IteratorMode::End,
+	};
+
+	let entries = iterate(read_options, mode)
+		.take(CHUNK_LEN)
+		.map(|kv| {
+			let (key, value) = kv?;
+
+			Ok((make_nebz_bytes(key.into())?, make_nebz_bytes(value.into())?))
+		})
+		.collect::<Result<_, RocksDbStoreError>>()?;
+
+	Ok(Chunk::new(entries))
+}
+
+/// Makes the [`ReadOptions`] bounding an iteration to `range`, or [`None`] if `range` is empty.
+fn make_read_options((start, end): (Bound<&[u8]>, Bound<&[u8]>)) -> Option<ReadOptions> {
+	// RocksDB takes an inclusive lower and an exclusive upper bound, and appending a zero byte to
+	// a key makes its immediate successor
+	let lower = match start {
+		Bound::Included(start) => Some(start.to_vec()),
+		Bound::Excluded(start) => Some([start, [0].as_slice()].concat()),
+		Bound::Unbounded => None,
+	};
+
+	let upper = match end {
+		Bound::Included(end) => Some([end, [0].as_slice()].concat()),
+		Bound::Excluded(end) => Some(end.to_vec()),
+		Bound::Unbounded => None,
+	};
+	if let (Some(lower), Some(upper)) = (&lower, &upper)
+		&& lower >= upper
+	{
+		return None;
+	}
+
+	let mut read_options = ReadOptions::default();
+
+	if let Some(lower) = lower {
+		read_options.set_iterate_lower_bound(lower);
+	}
+
+	if let Some(upper) = upper {
+		read_options.set_iterate_upper_bound(upper);
+	}
+
+	Some(read_options)
+}
+
+fn make_nebz_bytes(bz: Bytes) -> Result<NonEmptyBz<Bytes>, RocksDbStoreError> {
+	NonEmptyBz::new(bz).ok_or(RocksDbStoreError::EmptyValue)
+}
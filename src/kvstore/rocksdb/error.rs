@@ -0,0 +1,11 @@
+#[derive(Debug, thiserror::Error)]
+pub enum RocksDbStoreError {
+	#[error("database error: {0}")]
+	Database(#[from] rocksdb::Error),
+
+	#[error("missing column family error: column family `{0}` must be opened with the database")]
+	MissingColumnFamily(String),
+
+	#[error("empty value error: value must not be empty")]
+	EmptyValue,
+}
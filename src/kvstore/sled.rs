@@ -0,0 +1,122 @@
+mod error;
+
+pub use self::error::SledStoreError;
+
+use core::ops::RangeBounds;
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+use sled::{Batch, Db, IVec, Tree};
+
+use super::{KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp};
+
+/// A store backed by a [`sled`] tree.
+///
+/// As sled offers no point-in-time reads, [`KVStore::snapshot`] yields a handle reading the live
+/// tree: queries are not isolated from concurrent writes.
+#[derive(Debug, Clone)]
+pub struct SledStore {
+	tree: Tree,
+}
+
+impl SledStore {
+	pub fn new(db: &Db, tree_name: &str) -> Result<Self, SledStoreError> {
+		Ok(Self { tree: db.open_tree(tree_name)? })
+	}
+}
+
+impl KVStore for SledStore {
+	type Error = SledStoreError;
+
+	type Snapshot = SledStore;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(self.clone())
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.tree.get(key.get())?.map(make_nebz_bytes).transpose()
+	}
+
+	fn has<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.tree.contains_key(key.get()).map_err(From::from)
+	}
+}
+
+impl MutKVStore for SledStore {
+	type Error = SledStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let updated = self.tree.insert(key.get(), value.get().as_ref())?.is_some();
+
+		Ok(updated)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let removed = self.tree.remove(key.get())?.is_some();
+
+		Ok(removed)
+	}
+
+	/// Applies `batch` atomically as a single sled [`Batch`].
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut sled_batch = Batch::default();
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => {
+					sled_batch.insert(key.get().as_ref(), value.get().as_ref())
+				},
+				WriteOp::Remove { key } => sled_batch.remove(key.get().as_ref()),
+			}
+		}
+
+		self.tree.apply_batch(sled_batch).map_err(From::from)
+	}
+}
+
+impl KVIterator for SledStore {
+	type Error = SledStoreError;
+
+	type FetchError = SledStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		let start = range.start_bound().map(|key| *key.get());
+		let end = range.end_bound().map(|key| *key.get());
+
+		let iter = self.tree.range::<&[u8], _>((start, end)).map(|kv| {
+			let (key, value) = kv?;
+			Ok((make_nebz_bytes(key)?, make_nebz_bytes(value)?))
+		});
+
+		Ok(iter)
+	}
+}
+
+fn make_nebz_bytes(ivec: IVec) -> Result<NonEmptyBz<Bytes>, SledStoreError> {
+	NonEmptyBz::new(Bytes::from_owner(ivec)).ok_or(SledStoreError::EmptyValue)
+}
@@ -0,0 +1,8 @@
+#[derive(Debug, thiserror::Error)]
+pub enum SledStoreError {
+	#[error("database error: {0}")]
+	Database(#[from] sled::Error),
+
+	#[error("empty value error: value must not be empty")]
+	EmptyValue,
+}
@@ -0,0 +1,226 @@
+mod error;
+
+pub use self::error::SqliteStoreError;
+
+use core::ops::{Bound, RangeBounds};
+
+use std::{
+	path::Path,
+	sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+use rusqlite::{Connection, OptionalExtension};
+
+use super::{
+	KVIterator, KVStore, MutKVStore, WriteBatch, WriteOp,
+	chunked::{CHUNK_LEN, Chunk, ChunkedRange, Direction},
+};
+
+/// A store backed by a single `(key BLOB PRIMARY KEY, value BLOB)` table of a SQLite database.
+///
+/// Keys are compared byte-wise by SQLite, so the table can be queried in key order with plain
+/// SQL. As a snapshot would need a connection of its own, [`KVStore::snapshot`] yields a handle
+/// reading the live table: queries are not isolated from concurrent writes.
+#[derive(Debug, Clone)]
+pub struct SqliteStore {
+	conn: Arc<Mutex<Connection>>,
+	table: Arc<str>,
+}
+
+impl SqliteStore {
+	/// Creates a store over table `table_name` of `conn`, creating the table if missing.
+	pub fn new(conn: Connection, table_name: &str) -> Result<Self, SqliteStoreError> {
+		// quoted as an identifier, with embedded quotes doubled
+		let table = format!("\"{}\"", table_name.replace('"', "\"\""));
+
+		conn.execute_batch(&format!(
+			"CREATE TABLE IF NOT EXISTS {table} (
+				key BLOB NOT NULL PRIMARY KEY,
+				value BLOB NOT NULL
+			) WITHOUT ROWID",
+		))?;
+
+		Ok(Self { conn: Arc::new(Mutex::new(conn)), table: table.into() })
+	}
+
+	/// Opens the SQLite database at `path`, see [`SqliteStore::new`].
+	pub fn open<P>(path: P, table_name: &str) -> Result<Self, SqliteStoreError>
+	where
+		P: AsRef<Path>,
+	{
+		Self::new(Connection::open(path)?, table_name)
+	}
+}
+
+impl KVStore for SqliteStore {
+	type Error = SqliteStoreError;
+
+	type Snapshot = SqliteStore;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(self.clone())
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let conn = self.conn.lock()?;
+
+		conn.prepare_cached(&format!("SELECT value FROM {} WHERE key = ?1", self.table))?
+			.query_row([key.get().as_ref()], |row| row.get::<_, Vec<u8>>(0))
+			.optional()?
+			.map(make_nebz_bytes)
+			.transpose()
+	}
+}
+
+impl MutKVStore for SqliteStore {
+	type Error = SqliteStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		let mut conn = self.conn.lock()?;
+		let tx = conn.transaction()?;
+
+		let updated = tx
+			.prepare_cached(&format!(
+				"SELECT EXISTS (SELECT 1 FROM {} WHERE key = ?1)",
+				self.table
+			))?
+			.query_row([key.get().as_ref()], |row| row.get(0))?;
+
+		insert(&tx, &self.table, key.get().as_ref(), value.get().as_ref())?;
+
+		tx.commit()?;
+
+		Ok(updated)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		let conn = self.conn.lock()?;
+
+		let removed = remove(&conn, &self.table, key.get().as_ref())?;
+
+		Ok(removed)
+	}
+
+	/// Applies `batch` within a single transaction.
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		let mut conn = self.conn.lock()?;
+		let tx = conn.transaction()?;
+
+		for op in batch {
+			match op {
+				WriteOp::Insert { key, value } => insert(&tx, &self.table, key.get(), value.get())?,
+				WriteOp::Remove { key } => {
+					remove(&tx, &self.table, key.get())?;
+				},
+			}
+		}
+
+		tx.commit()?;
+
+		Ok(())
+	}
+}
+
+impl KVIterator for SqliteStore {
+	type Error = SqliteStoreError;
+
+	type FetchError = SqliteStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		// each chunk is queried on its own, as the connection cannot stay locked in between
+		Ok(ChunkedRange::new(range, |(start, end), direction| {
+			let (mut conditions, mut params) = (vec!["1"], vec![]);
+
+			match start {
+				Bound::Included(start) => {
+					conditions.push("key >= ?");
+					params.push(start);
+				},
+				Bound::Excluded(start) => {
+					conditions.push("key > ?");
+					params.push(start);
+				},
+				Bound::Unbounded => {},
+			}
+
+			match end {
+				Bound::Included(end) => {
+					conditions.push("key <= ?");
+					params.push(end);
+				},
+				Bound::Excluded(end) => {
+					conditions.push("key < ?");
+					params.push(end);
+				},
+				Bound::Unbounded => {},
+			}
+
+			let order = match direction {
+				Direction::Forward => "ASC",
+				Direction::Reverse => "DESC",
+			};
+
+			let conn = self.conn.lock()?;
+
+			let mut stmt = conn.prepare_cached(&format!(
+				"SELECT key, value FROM {} WHERE {} ORDER BY key {order} LIMIT {CHUNK_LEN}",
+				self.table,
+				conditions.join(" AND "),
+			))?;
+
+			let entries = stmt
+				.query_map(rusqlite::params_from_iter(params), |row| {
+					Ok((row.get::<_, Vec<u8>>(0)?, row.get::<_, Vec<u8>>(1)?))
+				})?
+				.map(|kv| {
+					let (key, value) = kv?;
+					Ok((make_nebz_bytes(key)?, make_nebz_bytes(value)?))
+				})
+				.collect::<Result<_, SqliteStoreError>>()?;
+
+			Ok(Chunk::new(entries))
+		}))
+	}
+}
+
+fn insert(conn: &Connection, table: &str, key: &[u8], value: &[u8]) -> rusqlite::Result<()> {
+	conn.prepare_cached(&format!(
+		"INSERT INTO {table} (key, value) VALUES (?1, ?2)
+			ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+	))?
+	.execute([key, value])
+	.map(|_| ())
+}
+
+fn remove(conn: &Connection, table: &str, key: &[u8]) -> rusqlite::Result<bool> {
+	conn.prepare_cached(&format!("DELETE FROM {table} WHERE key = ?1"))?
+		.execute([key])
+		.map(|removed| removed > 0)
+}
+
+fn make_nebz_bytes(bz: Vec<u8>) -> Result<NonEmptyBz<Bytes>, SqliteStoreError> {
+	NonEmptyBz::new(bz.into()).ok_or(SqliteStoreError::EmptyValue)
+}
@@ -7,7 +7,8 @@ mod node;
 
 pub use self::{
 	immutable::ImmutableTree,
-	mutable::{MutableTree, MutableTreeError},
+	mutable::{KeyChange, MutableTree, MutableTreeError},
+	node::ndb::{Compression, NodeCacheStats},
 };
 
 use core::num::NonZeroUsize;
@@ -28,8 +29,6 @@ const SHA256_HASH_LEN: NonZeroUsize = NonZeroUsize::new(32).unwrap();
 
 type NodeHash<const N: usize = { SHA256_HASH_LEN.get() }> = [u8; N];
 
-type NodeHashPair = (NodeHash, NodeHash);
-
 type NodeKeyPair = (NodeKey, NodeKey);
 
 pub trait Get: Sealed {
@@ -49,7 +48,7 @@ pub struct GetError(#[from] NodeError);
 
 /// NodeKey represents a key of node in the DB
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct NodeKey<V = U63, N = U31> {
+pub struct NodeKey<V = U63, N = U31> {
 	/// version of the IAVL that this node was first added in
 	version: V,
 
@@ -62,11 +61,11 @@ impl<V, N> NodeKey<V, N> {
 		Self { version, nonce }
 	}
 
-	const fn version(&self) -> &V {
+	pub const fn version(&self) -> &V {
 		&self.version
 	}
 
-	const fn nonce(&self) -> &N {
+	pub const fn nonce(&self) -> &N {
 		&self.nonce
 	}
 }
@@ -1,3 +1,4 @@
+mod asynchronous;
 mod error;
 
 use bytes::Bytes;
@@ -6,11 +7,17 @@ use oblux::{U7, U31, U63};
 
 pub use self::error::MutableTreeError;
 
-use core::{cmp, mem, ops::Deref};
+use core::{
+	cmp, mem,
+	num::NonZeroUsize,
+	ops::{Bound, Deref, RangeBounds, RangeInclusive},
+};
+
+use std::collections::BTreeSet;
 
 use crate::{
-	NodeHash, Sealed,
-	kvstore::{KVIterator, KVStore, MutKVStore},
+	Compression, NodeCacheStats, NodeHash, NodeKey, Sealed,
+	kvstore::{KVIterator, KVStore, MutKVStore, WriteBatch},
 };
 
 use super::{
@@ -25,6 +32,16 @@ use super::{
 
 use self::error::{MutableTreeErrorKind, Result};
 
+/// Change undergone by the leaf of a key in a version, see [`MutableTree::key_history`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyChange {
+	/// The key was inserted or its value was updated.
+	Written,
+
+	/// The key was removed.
+	Deleted,
+}
+
 pub struct MutableTree<DB> {
 	root: Option<ArlockNode>,
 	last_saved: Option<ImmutableTree<DB>>,
@@ -48,6 +65,18 @@ impl<DB> MutableTree<DB> {
 		self.last_saved.as_ref()
 	}
 
+	pub fn into_last_saved(self) -> Option<ImmutableTree<DB>> {
+		self.last_saved
+	}
+
+	/// Returns the store of the tree, e.g. to compact it before reopening the tree through
+	/// [`MutableTree::load_latest_version`].
+	///
+	/// Unsaved changes are dropped along with the tree, as are its settings such as compression.
+	pub fn into_db(self) -> DB {
+		self.ndb.into_db()
+	}
+
 	pub fn version(&self) -> U63 {
 		self.version
 	}
@@ -60,6 +89,42 @@ impl<DB> MutableTree<DB> {
 		self.last_saved().map(ImmutableTree::hash).unwrap_or(Self::EMPTY_ROOT_HASH)
 	}
 
+	/// Sets the [`Compression`] of the nodes saved from now on, which leaves saved nodes and
+	/// hashes intact.
+	pub fn set_compression(&mut self, compression: Option<Compression>) {
+		self.ndb.set_compression(compression);
+	}
+
+	/// Sets whether the nodes saved from now on carry a checksum, so that their corruption in
+	/// storage fails fetching them rather than going unnoticed.
+	pub fn set_checksums(&mut self, checksums: bool) {
+		self.ndb.set_checksums(checksums);
+	}
+
+	/// Sets the minimum length of the values that leaves saved from now on store out of line,
+	/// once per distinct value, or disables doing so if `None`. Hashes stay the same.
+	///
+	/// Separated values are kept even once no leaf references them anymore, as leaves of other
+	/// versions may share them.
+	pub fn set_value_separation(&mut self, min_len: Option<NonZeroUsize>) {
+		self.ndb.set_separated_value_min_len(min_len);
+	}
+
+	/// Sets the size limit in bytes of a cache of the least recently used nodes fetched from the
+	/// store, so that hot nodes are neither read nor hashed anew, or drops the cache if `None`.
+	///
+	/// The cache starts empty and is shared with the trees of the versions saved from now on.
+	/// Nodes are cached by [`NodeKey`], and only rollbacks of this tree evict the versions they
+	/// remove, so other trees rolling back and saving the same store leave stale nodes behind.
+	pub fn set_node_cache(&mut self, max_bytes: Option<NonZeroUsize>) {
+		self.ndb.set_node_cache(max_bytes);
+	}
+
+	/// Returns the hit and miss counters and occupancy of the node cache, if any.
+	pub fn node_cache_stats(&self) -> Result<Option<NodeCacheStats>> {
+		self.ndb.node_cache_stats().map_err(|err| MutableTreeErrorKind::from(err).into())
+	}
+
 	fn with_ndb(ndb: NodeDb<DB>) -> Self {
 		Self { root: None, last_saved: None, version: U63::MIN, ndb, size: U63::MIN }
 	}
@@ -67,13 +132,171 @@ impl<DB> MutableTree<DB> {
 	fn root(&self) -> Option<&ArlockNode> {
 		self.root.as_ref()
 	}
+
+	fn insert_into_empty(&mut self, key: NonEmptyBz<Bytes>, value: Bytes) {
+		let leaf = LeafNode::builder().key(key).value(value).build();
+		self.root = Some(leaf.into());
+		self.size = U63::ONE;
+	}
+
+	fn apply_insert(
+		&mut self,
+		new_root: DraftedNode,
+		updated: bool,
+	) -> Result<bool, MutableTreeErrorKind> {
+		self.root = Some(new_root.into());
+
+		if !updated {
+			self.size = self
+				.size
+				.get()
+				.checked_add(1)
+				.and_then(U63::new)
+				.ok_or(MutableTreeErrorKind::Overflow)?;
+		}
+
+		Ok(updated)
+	}
+
+	fn apply_remove(&mut self, new_root: Option<ArlockNode>, removed: bool) -> bool {
+		self.root = new_root;
+
+		if removed {
+			// unwrap is safe here because original size must be positive for a key to be removed
+			self.size = self.size.get().checked_sub(1).and_then(U63::new).unwrap();
+		}
+
+		removed
+	}
 }
 
 impl<DB> MutableTree<DB>
 where
-	DB: KVStore + KVIterator + Clone,
+	DB: Clone,
 {
+	/// Returns the tree of the latest `version` rooted at `root`, [`None`] if empty.
+	fn with_latest_root(
+		ndb: NodeDb<DB>,
+		version: U63,
+		root: Option<SavedNode>,
+	) -> Result<Self, MutableTreeErrorKind> {
+		let Some(root) = root else {
+			// the latest version is empty, yet the next save must not overwrite it
+			let mut tree = Self::with_ndb(ndb);
+			tree.version = version;
+
+			return Ok(tree);
+		};
+
+		let root = ArlockNode::from(root);
+
+		let last_saved = ImmutableTree::builder()
+			.root(root.clone())
+			.ndb(ndb.clone())
+			.version(version)
+			.build()?;
+
+		let size = last_saved.size();
+
+		Ok(Self { root: Some(root), last_saved: Some(last_saved), version, ndb, size })
+	}
+
+	fn working_version(&self) -> Result<U63, MutableTreeErrorKind> {
+		self.version().get().checked_add(1).and_then(U63::new).ok_or(MutableTreeErrorKind::Overflow)
+	}
+
+	/// Stages the next version into `batch`, passing each newly saved node to `stage_node`.
+	///
+	/// Returns the version along with its tree, [`None`] if empty, which only takes effect
+	/// through [`MutableTree::apply_saved_version`] once `batch` is committed.
+	fn stage_version<F>(
+		&self,
+		batch: &mut WriteBatch,
+		metadata: Option<NonEmptyBz<&[u8]>>,
+		mut stage_node: F,
+	) -> Result<(U63, Option<ImmutableTree<DB>>), MutableTreeErrorKind>
+	where
+		F: FnMut(&mut WriteBatch, &SavedNode) -> Result<(), MutableTreeErrorKind>,
+	{
+		let working_version = self.working_version()?;
+
+		if let Some(metadata) = metadata {
+			self.ndb.stage_version_metadata(batch, working_version, metadata);
+		}
+
+		let Some(root) = self.root() else {
+			self.ndb.stage_empty_root(batch, working_version);
+			self.ndb.stage_commit_marker(batch, working_version, &Self::EMPTY_ROOT_HASH);
+
+			return Ok((working_version, None));
+		};
+
+		let new_root = match root.read()?.deref() {
+			Node::Saved(saved) => {
+				self.ndb.stage_reference_root(batch, working_version, &saved.node_key());
+
+				root.clone()
+			},
+			Node::Drafted(drafted) => {
+				// TODO: devise a strategy to avoid creating new `DraftedNode` from `&DraftedNode`.
+				let drafted = drafted.into();
+				let mut nonce = U31::MIN;
+
+				recursive_make_saved_nodes(
+					drafted,
+					batch,
+					working_version,
+					&mut nonce,
+					&mut stage_node,
+				)?
+				.into()
+			},
+		};
+
+		let new_last_saved = ImmutableTree::builder()
+			.root(new_root)
+			.ndb(self.ndb.clone()) // TODO: devise a strategy to avoid `ndb`'s clone
+			.version(working_version)
+			.build()?;
+
+		self.ndb.stage_commit_marker(batch, working_version, &new_last_saved.hash());
+
+		Ok((working_version, Some(new_last_saved)))
+	}
+
+	fn apply_saved_version(&mut self, version: U63, saved: Option<ImmutableTree<DB>>) -> U63 {
+		self.root = saved.as_ref().map(|saved| saved.root().clone());
+		self.last_saved = saved;
+		self.version = version;
+
+		version
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: MutKVStore + KVStore + KVIterator + Clone,
+{
+	/// Loads the latest committed version, removing whatever interrupted saves left behind
+	/// beyond it.
 	pub fn load_latest_version(db: DB) -> Result<Self> {
+		let tree = Self::load_latest_version_read_only(db)?;
+
+		tree.ndb.remove_versions_after(tree.version).map_err(MutableTreeErrorKind::from)?;
+
+		Ok(tree)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: KVStore + KVIterator + Clone,
+{
+	/// Loads the latest committed version without ever writing to `db`.
+	///
+	/// Leftovers of interrupted saves are skipped rather than removed. As the tree can only be
+	/// modified over a [`MutKVStore`], a store lacking it makes for a query-only tree.
+	pub fn load_latest_version_read_only(db: DB) -> Result<Self> {
 		let ndb = NodeDb::builder().db(db).build();
 
 		let Some((latest_root_nk, latest_root_node)) =
@@ -82,42 +305,51 @@ where
 			return Ok(Self::with_ndb(ndb));
 		};
 
-		let root = match latest_root_node {
-			FetchedNode::EmptyRoot => return Ok(Self::with_ndb(ndb)),
-			FetchedNode::Deserialized(denode) => {
-				denode.into_saved_checked(&latest_root_nk).map_err(MutableTreeErrorKind::from)?
-			},
-			FetchedNode::ReferenceRoot(nk) => {
-				match ndb.fetch_one_node(&nk).map_err(MutableTreeErrorKind::from)? {
-					Some(node) => match node {
-						FetchedNode::Deserialized(denode) => {
-							denode.into_saved_checked(&nk).map_err(MutableTreeErrorKind::from)?
-						},
-						_ => Err(MutableTreeErrorKind::ConflictingRoot)?,
-					},
-					None => return Ok(Self::with_ndb(ndb)),
-				}
-			},
-		};
+		let root = resolve_root_node(&ndb, &latest_root_nk, latest_root_node)?;
 
-		let root = ArlockNode::from(root);
+		Self::with_latest_root(ndb, *latest_root_nk.version(), root).map_err(From::from)
+	}
 
-		let last_saved = ImmutableTree::builder()
-			.root(root.clone())
-			.ndb(ndb.clone())
-			.version(*latest_root_nk.version())
-			.build()
-			.map_err(MutableTreeErrorKind::from)?;
+	/// Copies every saved version of the tree into `target`, e.g. to move it to another
+	/// backend, and returns the tree loaded over `target`.
+	///
+	/// Nodes are copied as stored, hence with their compression and checksums, and versions are
+	/// never re-executed. The copy is verified by recomputing the root hash of every version from
+	/// the nodes in `target` and matching it against the source, failing on the first version
+	/// that differs. Each node is hashed along with the version it was written in.
+	pub fn copy_to<T>(&self, target: T) -> Result<MutableTree<T>>
+	where
+		T: MutKVStore + KVStore + KVIterator + Clone,
+	{
+		self.ndb.copy_into(&target).map_err(MutableTreeErrorKind::from)?;
 
-		let size = last_saved.size();
+		let copied = MutableTree::load_latest_version(target)?;
+
+		let mut copied_roots = copied.roots(..);
+		let mut verified = BTreeSet::new();
+
+		for root in self.roots(..) {
+			let (version, hash) = root?;
+
+			match copied_roots.next().transpose()? {
+				Some(copied_root) if copied_root == (version, hash) => {},
+				_ => return Err(MutableTreeErrorKind::CopyMismatch(version).into()),
+			}
+
+			if recompute_root_hash(&copied.ndb, version, &verified)? != hash {
+				return Err(MutableTreeErrorKind::CopyMismatch(version).into());
+			}
+
+			verified.insert(version);
+		}
+
+		if let Some((version, _)) = copied_roots.next().transpose()? {
+			return Err(MutableTreeErrorKind::CopyMismatch(version).into());
+		}
 
-		Ok(Self {
-			root: Some(root),
-			last_saved: Some(last_saved),
-			version: *latest_root_nk.version(),
-			ndb,
-			size,
-		})
+		drop(copied_roots);
+
+		Ok(copied)
 	}
 }
 
@@ -130,26 +362,13 @@ where
 	/// Returns [`true`] if an existing key is updated.
 	pub fn insert(&mut self, key: NonEmptyBz<Bytes>, value: Bytes) -> Result<bool> {
 		let Some(root) = self.root.take() else {
-			let leaf = LeafNode::builder().key(key).value(value).build();
-			self.root = Some(leaf.into());
-			self.size = U63::ONE;
+			self.insert_into_empty(key, value);
 			return Ok(false);
 		};
 
 		let (new_root, updated) = recursive_insert(&root, &self.ndb, key, value)?;
 
-		self.root = Some(new_root.into());
-
-		if !updated {
-			self.size = self
-				.size
-				.get()
-				.checked_add(1)
-				.and_then(U63::new)
-				.ok_or(MutableTreeErrorKind::Overflow)?;
-		}
-
-		Ok(updated)
+		self.apply_insert(new_root, updated).map_err(From::from)
 	}
 
 	/// Removes the node with given key-value pair.
@@ -165,79 +384,42 @@ where
 
 		let (new_root, removed) = recursive_remove(root, &self.ndb, key)?;
 
-		self.root = new_root;
-
-		if removed {
-			// unwrap is safe here because original size must be positive for a key to be removed
-			self.size = self.size.get().checked_sub(1).and_then(U63::new).unwrap();
-		}
-
-		Ok(removed)
+		Ok(self.apply_remove(new_root, removed))
 	}
 
 	pub fn save(&mut self) -> Result<U63> {
-		let working_version = self
-			.version()
-			.get()
-			.checked_add(1)
-			.and_then(U63::new)
-			.ok_or(MutableTreeErrorKind::Overflow)?;
-
-		let Some(root) = self.root.take() else {
-			self.ndb
-				.save_overwriting_empty_root(working_version)
-				.map_err(MutableTreeErrorKind::from)?;
-			self.version = working_version;
-
-			if let Some(tree) = self.last_saved.as_mut() {
-				tree.set_version(working_version)
-			}
-
-			return Ok(working_version);
-		};
+		self.save_version(None)
+	}
 
-		match root.read().map_err(MutableTreeErrorKind::from)?.deref() {
-			Node::Saved(saved) => {
-				self.ndb
-					.save_overwriting_reference_root(working_version, &saved.node_key())
-					.map_err(MutableTreeErrorKind::from)?;
-			},
-			Node::Drafted(drafted) => {
-				// TODO: devise a strategy to avoid creating new `DraftedNode` from `&DraftedNode`.
-				let drafted = drafted.into();
-				let mut nonce = U31::MIN;
-				let new_root: ArlockNode =
-					recursive_make_saved_nodes(drafted, &self.ndb, working_version, &mut nonce)?
-						.into();
-
-				let new_last_saved = ImmutableTree::builder()
-					.root(new_root.clone())
-					.ndb(self.ndb.clone()) // TODO: devise a strategy to avoid `ndb`'s clone
-					.version(working_version)
-					.build()
-					.map_err(MutableTreeErrorKind::from)?;
-
-				self.root = Some(new_root);
-				self.last_saved = Some(new_last_saved);
-				self.version = working_version;
-
-				return Ok(working_version);
-			},
-		};
+	/// Saves the tree like [`MutableTree::save`], storing `metadata` (e.g. block hash or
+	/// timestamp) along with the new version.
+	///
+	/// The metadata is written ahead of the version's root, so a saved root always has its
+	/// metadata stored.
+	pub fn save_with_metadata<M>(&mut self, metadata: NonEmptyBz<M>) -> Result<U63>
+	where
+		M: AsRef<[u8]>,
+	{
+		self.save_version(Some(metadata.as_ref_slice()))
+	}
 
-		// TODO: devise a strategy to avoid these repetitive lines
-		let new_last_saved = ImmutableTree::builder()
-			.root(root.clone())
-			.ndb(self.ndb.clone()) // TODO: devise a strategy to avoid `ndb`'s clone
-			.version(working_version)
-			.build()
-			.map_err(MutableTreeErrorKind::from)?;
+	fn save_version(&mut self, metadata: Option<NonEmptyBz<&[u8]>>) -> Result<U63> {
+		// the whole version is committed at once and only then applied to the tree, so that a
+		// failed save leaves nothing behind
+		let mut batch = WriteBatch::new();
+
+		// nodes left behind by an interrupted save are only discarded on loading
+		let (version, saved) =
+			self.stage_version(&mut batch, metadata, |batch, saved| {
+				match self.ndb.stage_non_overwriting_one_node(batch, saved)? {
+					Some(_) => Err(MutableTreeErrorKind::ConflictingNode(saved.version())),
+					None => Ok(()),
+				}
+			})?;
 
-		self.root = Some(root);
-		self.last_saved = Some(new_last_saved);
-		self.version = working_version;
+		self.ndb.commit(batch).map_err(MutableTreeErrorKind::from)?;
 
-		Ok(working_version)
+		Ok(self.apply_saved_version(version, saved))
 	}
 
 	/// `root` must be of Saved type.
@@ -265,6 +447,316 @@ where
 	}
 }
 
+impl<DB> MutableTree<DB>
+where
+	DB: KVStore,
+{
+	/// Gets the index and value of `key` as of the saved `version`.
+	///
+	/// Unlike loading the whole version, only the root of `version` is looked up before
+	/// traversing down to `key`.
+	pub fn get_versioned<K>(&self, key: NonEmptyBz<K>, version: U63) -> Result<(U63, Option<Bytes>)>
+	where
+		K: AsRef<[u8]>,
+	{
+		if let Some(last_saved) = self.last_saved().filter(|tree| tree.version() == version) {
+			return last_saved
+				.get(key)
+				.map_err(|GetError(err)| MutableTreeErrorKind::from(err).into());
+		}
+
+		// root entries past the latest version belong to a half-saved version
+		if version > self.version() {
+			return Err(MutableTreeErrorKind::MissingVersion(version).into());
+		}
+
+		let ndb = self.ndb.snapshot().map_err(MutableTreeErrorKind::from)?;
+
+		let (root_nk, root_node) = ndb
+			.fetch_root_node(version)
+			.map_err(MutableTreeErrorKind::from)?
+			.ok_or(MutableTreeErrorKind::MissingVersion(version))?;
+
+		let Some(root) = resolve_root_node(&ndb, &root_nk, root_node)? else {
+			return Ok((U63::MIN, None));
+		};
+
+		Node::from(root).get(&ndb, key).map_err(MutableTreeErrorKind::from).map_err(From::from)
+	}
+
+	/// Gets the index and value of `key` as of the last saved version, along with the version
+	/// its leaf was written in and the leaf's [`NodeKey`].
+	///
+	/// Unsaved changes are not visible, as their leaves are yet to get a [`NodeKey`].
+	#[allow(clippy::type_complexity)]
+	pub fn get_with_meta<K>(
+		&self,
+		key: NonEmptyBz<K>,
+	) -> Result<(U63, Option<(Bytes, U63, NodeKey)>)>
+	where
+		K: AsRef<[u8]>,
+	{
+		let Some(last_saved) = self.last_saved() else {
+			return Ok((U63::MIN, None));
+		};
+
+		last_saved
+			.get_with_meta(key)
+			.map_err(|GetError(err)| MutableTreeErrorKind::from(err).into())
+	}
+
+	/// Returns the root hash of the saved `version`, or [`None`] if `version` does not exist or
+	/// is past [`MutableTree::version`].
+	///
+	/// Only the root entry of `version` (and the root it references, if any) is read.
+	pub fn root_hash(&self, version: U63) -> Result<Option<NodeHash>> {
+		// root entries past the latest version belong to a half-saved version
+		if version > self.version() {
+			return Ok(None);
+		}
+
+		let ndb = self.ndb.snapshot().map_err(MutableTreeErrorKind::from)?;
+
+		ndb.fetch_root_node(version)
+			.map_err(MutableTreeErrorKind::from)?
+			.map(|(root_nk, root_node)| root_node_hash(&ndb, &root_nk, root_node))
+			.transpose()
+			.map_err(From::from)
+	}
+
+	/// Returns every version within `range` in which the leaf of `key` was written or deleted,
+	/// in ascending order.
+	///
+	/// The trees of successive versions are followed, skipping the ones sharing the root with
+	/// their preceding version. A deletion is attributed to the first retained version lacking
+	/// the key.
+	pub fn key_history<K, R>(&self, key: NonEmptyBz<K>, range: R) -> Result<Vec<(U63, KeyChange)>>
+	where
+		K: AsRef<[u8]>,
+		R: RangeBounds<U63>,
+	{
+		let mut versions = self.saved_versions(range).peekable();
+
+		let Some(&first) = versions.peek() else {
+			return Ok(vec![]);
+		};
+
+		let ndb = self.ndb.snapshot().map_err(MutableTreeErrorKind::from)?;
+
+		// the state preceding the range decides whether the first version deletes the key
+		let (mut prev_root_nk, mut prev_leaf_nk) = first
+			.get()
+			.checked_sub(1)
+			.and_then(U63::new)
+			.map(|version| fetch_root_and_leaf_node_keys(&ndb, key.as_ref_slice(), version, None))
+			.transpose()?
+			.flatten()
+			.unwrap_or_default();
+
+		let mut history = vec![];
+
+		for version in versions {
+			let Some((root_nk, leaf_nk)) = fetch_root_and_leaf_node_keys(
+				&ndb,
+				key.as_ref_slice(),
+				version,
+				prev_root_nk.as_ref(),
+			)?
+			else {
+				continue;
+			};
+
+			if root_nk.is_some() && root_nk == prev_root_nk {
+				continue;
+			}
+
+			match (&prev_leaf_nk, &leaf_nk) {
+				(_, Some(nk)) if *nk.version() == version => {
+					history.push((version, KeyChange::Written))
+				},
+				(Some(_), None) => history.push((version, KeyChange::Deleted)),
+				_ => (),
+			}
+
+			(prev_root_nk, prev_leaf_nk) = (root_nk, leaf_nk);
+		}
+
+		Ok(history)
+	}
+
+	/// Returns the metadata saved along with `version` through
+	/// [`MutableTree::save_with_metadata`].
+	pub fn version_metadata(&self, version: U63) -> Result<Option<NonEmptyBz<Bytes>>> {
+		self.ndb
+			.fetch_version_metadata(version)
+			.map_err(MutableTreeErrorKind::from)
+			.map_err(From::from)
+	}
+
+	/// Returns the version tagged with checkpoint `tag`, if any.
+	pub fn checkpoint_version<T>(&self, tag: NonEmptyBz<T>) -> Result<Option<U63>>
+	where
+		T: AsRef<[u8]>,
+	{
+		self.ndb.fetch_checkpoint(tag).map_err(MutableTreeErrorKind::from).map_err(From::from)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: KVStore + KVIterator,
+{
+	/// Returns an iterator over the saved versions within `range` along with their root hashes.
+	///
+	/// Versions absent from the node db are skipped. The root hash recorded by the commit marker
+	/// of a version is checked against its root entry, failing on a mismatch.
+	pub fn roots<R>(&self, range: R) -> impl Iterator<Item = Result<(U63, NodeHash)>>
+	where
+		R: RangeBounds<U63>,
+	{
+		let versions = self.saved_version_bounds(range);
+		let (start, end) = (*versions.start(), *versions.end());
+
+		// the commit markers are read at once, except for the versions saved before markers were
+		// introduced, which precede the marked ones and are looked up one by one
+		let committed = match (U63::new(start), U63::new(end)) {
+			(Some(start), Some(end)) => self.ndb.fetch_committed_root_hashes(start, end),
+			_ => Ok(vec![]),
+		};
+
+		let (committed, unmarked_end, err) = match committed {
+			Ok(committed) => {
+				let unmarked_end = committed.first().map_or(end + 1, |(version, _)| version.get());
+				(committed, unmarked_end, None)
+			},
+			Err(err) => (vec![], start, Some(MutableTreeErrorKind::from(err).into())),
+		};
+
+		let unmarked = (start..unmarked_end).filter_map(|version| {
+			// unwrap is safe because versions are bounded by the latest version
+			let version = U63::new(version).unwrap();
+			self.root_hash(version).map(|hash| hash.map(|hash| (version, hash))).transpose()
+		});
+
+		let committed =
+			committed.into_iter().map(|(version, marker_hash)| match self.root_hash(version)? {
+				Some(hash) if hash == marker_hash => Ok((version, hash)),
+				_ => Err(MutableTreeErrorKind::CommitMismatch(version).into()),
+			});
+
+		err.map(Err).into_iter().chain(unmarked).chain(committed)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: MutKVStore + KVStore + KVIterator,
+{
+	/// Tags the saved `version` with checkpoint `tag`, e.g. `pre-upgrade-v7`.
+	///
+	/// Only fully saved versions, i.e. carrying a commit marker, can be tagged. Checkpointed
+	/// versions must never be deleted by a pruning strategy, see
+	/// [`MutableTree::is_checkpointed`]. Tagging is idempotent, however a `tag` must be removed
+	/// through [`MutableTree::remove_checkpoint`] before moving it to another version.
+	pub fn checkpoint<T>(&self, tag: NonEmptyBz<T>, version: U63) -> Result<()>
+	where
+		T: AsRef<[u8]>,
+	{
+		match self.ndb.fetch_checkpoint(tag.as_ref_slice()).map_err(MutableTreeErrorKind::from)? {
+			Some(tagged) if tagged == version => return Ok(()),
+			Some(_) => return Err(MutableTreeErrorKind::ConflictingCheckpoint.into()),
+			None => (),
+		}
+
+		// the root of a half-saved version may already be written, hence only stores written
+		// before commit markers were introduced are left to go by it
+		let saved =
+			match self.ndb.fetch_latest_committed_version().map_err(MutableTreeErrorKind::from)? {
+				Some(_) => {
+					self.ndb.has_commit_marker(version).map_err(MutableTreeErrorKind::from)?
+				},
+				None => {
+					self.ndb.fetch_root_node(version).map_err(MutableTreeErrorKind::from)?.is_some()
+				},
+			};
+
+		if !saved {
+			return Err(MutableTreeErrorKind::MissingVersion(version).into());
+		}
+
+		self.ndb
+			.save_overwriting_checkpoint(tag, version)
+			.map(|_| ())
+			.map_err(MutableTreeErrorKind::from)
+			.map_err(From::from)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: MutKVStore + KVStore,
+{
+	/// Removes checkpoint `tag`, making its version prunable unless tagged otherwise.
+	///
+	/// Returns [`false`] when `tag` is not found.
+	pub fn remove_checkpoint<T>(&self, tag: NonEmptyBz<T>) -> Result<bool>
+	where
+		T: AsRef<[u8]>,
+	{
+		self.ndb.remove_checkpoint(tag).map_err(MutableTreeErrorKind::from).map_err(From::from)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: KVIterator,
+{
+	/// Returns all checkpoint tags along with their versions, ordered by tag.
+	pub fn checkpoints(&self) -> Result<Vec<(NonEmptyBz<Bytes>, U63)>> {
+		self.ndb.fetch_checkpoints().map_err(MutableTreeErrorKind::from).map_err(From::from)
+	}
+
+	/// Returns [`true`] if `version` is tagged with any checkpoint, in which case it must be
+	/// exempted from pruning.
+	pub fn is_checkpointed(&self, version: U63) -> Result<bool> {
+		self.checkpoints().map(|checkpoints| checkpoints.iter().any(|(_, v)| *v == version))
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: KVStore,
+{
+	/// Returns the saved versions within `range`, some of which may be absent from the node db.
+	fn saved_versions<R>(&self, range: R) -> impl Iterator<Item = U63>
+	where
+		R: RangeBounds<U63>,
+	{
+		// unwrap is safe because versions are bounded by the latest version
+		self.saved_version_bounds(range).map(|version| U63::new(version).unwrap())
+	}
+
+	/// Returns the bounds of [`MutableTree::saved_versions`].
+	fn saved_version_bounds<R>(&self, range: R) -> RangeInclusive<u64>
+	where
+		R: RangeBounds<U63>,
+	{
+		let start = match range.start_bound() {
+			Bound::Included(start) => start.get(),
+			Bound::Excluded(start) => start.get().saturating_add(1),
+			Bound::Unbounded => U63::ONE.get(),
+		};
+
+		let end = match range.end_bound() {
+			Bound::Included(end) => end.get(),
+			Bound::Excluded(end) => end.get().saturating_sub(1),
+			Bound::Unbounded => self.version().get(),
+		};
+
+		cmp::max(start, U63::ONE.get())..=cmp::min(end, self.version().get())
+	}
+}
+
 impl<DB> Get for MutableTree<DB>
 where
 	DB: KVStore,
@@ -281,7 +773,9 @@ where
 			return Ok((U63::MIN, None));
 		};
 
-		root.read().map_err(NodeError::from)?.get(&self.ndb, key).map_err(From::from)
+		let ndb = self.ndb.snapshot().map_err(NodeError::from)?;
+
+		root.read().map_err(NodeError::from)?.get(&ndb, key).map_err(From::from)
 	}
 }
 
@@ -347,6 +841,15 @@ where
 	if !removed {
 		mem::drop(gnode);
 
+		// children are restored since drafted nodes must keep them full until saved
+		if let (Some(left), Some(right)) = (new_left, new_right) {
+			let mut gnode_mut = node.write()?;
+
+			// unwraps are safe because node is inner node
+			*gnode_mut.left_mut().unwrap() = Child::Full(left);
+			*gnode_mut.right_mut().unwrap() = Child::Full(right);
+		}
+
 		return Ok((Some(node), false));
 	}
 
@@ -385,6 +888,161 @@ where
 	}
 }
 
+/// Resolves the root entry `root_node` fetched against `root_nk` into the saved root node.
+///
+/// Returns [`None`] for an empty root.
+fn resolve_root_node<DB>(
+	ndb: &NodeDb<DB>,
+	root_nk: &NodeKey,
+	root_node: FetchedNode,
+) -> Result<Option<SavedNode>, MutableTreeErrorKind>
+where
+	DB: KVStore,
+{
+	let root = match root_node {
+		FetchedNode::EmptyRoot => return Ok(None),
+		FetchedNode::Deserialized(denode) => denode.into_saved(root_nk)?,
+		FetchedNode::ReferenceRoot(nk) => match ndb.fetch_one_node(&nk)? {
+			Some(FetchedNode::Deserialized(denode)) => denode.into_saved(&nk)?,
+			Some(_) => return Err(MutableTreeErrorKind::ConflictingRoot),
+			None => return Ok(None),
+		},
+	};
+
+	Ok(Some(root))
+}
+
+/// Fetches the [`NodeKey`]s of the root of `version` and of the leaf of `key` in it.
+///
+/// The root is not traversed if it is `known_root_nk`, in which case the leaf is unknown.
+/// Returns [`None`] if `version` does not exist.
+#[allow(clippy::type_complexity)]
+fn fetch_root_and_leaf_node_keys<DB>(
+	ndb: &NodeDb<DB>,
+	key: NonEmptyBz<&[u8]>,
+	version: U63,
+	known_root_nk: Option<&NodeKey>,
+) -> Result<Option<(Option<NodeKey>, Option<NodeKey>)>, MutableTreeErrorKind>
+where
+	DB: KVStore,
+{
+	let Some((root_nk, root_node)) = ndb.fetch_root_node(version)? else {
+		return Ok(None);
+	};
+
+	if let FetchedNode::ReferenceRoot(nk) = &root_node
+		&& Some(nk) == known_root_nk
+	{
+		return Ok(Some((Some(nk.clone()), None)));
+	}
+
+	let Some(root) = resolve_root_node(ndb, &root_nk, root_node)? else {
+		return Ok(Some((None, None)));
+	};
+
+	let root_nk = root.node_key();
+	let (_, leaf) = Node::from(root).get_leaf(ndb, key)?;
+
+	Ok(Some((Some(root_nk), leaf.and_then(|(_, nk)| nk))))
+}
+
+/// Returns the hash of the root entry `root_node` fetched against `root_nk`.
+fn root_node_hash<DB>(
+	ndb: &NodeDb<DB>,
+	root_nk: &NodeKey,
+	root_node: FetchedNode,
+) -> Result<NodeHash, MutableTreeErrorKind>
+where
+	DB: KVStore,
+{
+	match root_node {
+		FetchedNode::EmptyRoot => Ok(MutableTree::<DB>::EMPTY_ROOT_HASH),
+		FetchedNode::Deserialized(denode) => Ok(denode.hash(*root_nk.version())),
+		FetchedNode::ReferenceRoot(nk) => match ndb.fetch_one_node(&nk)? {
+			Some(FetchedNode::Deserialized(denode)) => Ok(denode.hash(*nk.version())),
+			Some(_) => Err(MutableTreeErrorKind::ConflictingRoot),
+			None => Ok(MutableTree::<DB>::EMPTY_ROOT_HASH),
+		},
+	}
+}
+
+/// Recomputes the root hash of `version` from its nodes, descending into the ones written in
+/// versions not `verified` yet.
+///
+/// Fails with [`MutableTreeErrorKind::CopyMismatch`] on a missing node or a mismatching hash.
+fn recompute_root_hash<DB>(
+	ndb: &NodeDb<DB>,
+	version: U63,
+	verified: &BTreeSet<U63>,
+) -> Result<NodeHash, MutableTreeErrorKind>
+where
+	DB: KVStore,
+{
+	let (root_nk, root_node) =
+		ndb.fetch_root_node(version)?.ok_or(MutableTreeErrorKind::CopyMismatch(version))?;
+
+	match root_node {
+		FetchedNode::EmptyRoot => Ok(MutableTree::<DB>::EMPTY_ROOT_HASH),
+		FetchedNode::Deserialized(denode) => {
+			recompute_node_hash(ndb, version, &root_nk, denode, verified)
+		},
+		FetchedNode::ReferenceRoot(nk) => match ndb.fetch_one_node(&nk)? {
+			Some(FetchedNode::Deserialized(denode)) if verified.contains(nk.version()) => {
+				Ok(denode.hash(*nk.version()))
+			},
+			Some(FetchedNode::Deserialized(denode)) => {
+				recompute_node_hash(ndb, version, &nk, denode, verified)
+			},
+			Some(_) => Err(MutableTreeErrorKind::ConflictingRoot),
+			None => Ok(MutableTree::<DB>::EMPTY_ROOT_HASH),
+		},
+	}
+}
+
+/// Recomputes the hash of `node`, saved against `nk`, from its children, see
+/// [`recompute_root_hash`].
+fn recompute_node_hash<DB>(
+	ndb: &NodeDb<DB>,
+	version: U63,
+	nk: &NodeKey,
+	node: DeserializedNode,
+	verified: &BTreeSet<U63>,
+) -> Result<NodeHash, MutableTreeErrorKind>
+where
+	DB: KVStore,
+{
+	let DeserializedNode::Inner(inner, hash) = node else {
+		return Ok(node.hash(*nk.version()));
+	};
+
+	let child_hash = |child: &Child| {
+		let child_nk = child.node_key()?.ok_or(MutableTreeErrorKind::MissingNodeKey)?;
+
+		let Some(FetchedNode::Deserialized(child)) = ndb.fetch_one_node(&child_nk)? else {
+			return Err(MutableTreeErrorKind::CopyMismatch(version));
+		};
+
+		// nodes of verified versions had their hashes recomputed along with them
+		if verified.contains(child_nk.version()) {
+			Ok(child.hash(*child_nk.version()))
+		} else {
+			recompute_node_hash(ndb, version, &child_nk, child, verified)
+		}
+	};
+
+	let recomputed = inner.hash_with_children(
+		*nk.version(),
+		&child_hash(inner.left())?,
+		&child_hash(inner.right())?,
+	);
+
+	if recomputed != hash {
+		return Err(MutableTreeErrorKind::CopyMismatch(version));
+	}
+
+	Ok(recomputed)
+}
+
 fn save_new_root_node_checked<DB>(
 	saved_root_node: &SavedNode,
 	ndb: &NodeDb<DB>,
@@ -392,25 +1050,15 @@ fn save_new_root_node_checked<DB>(
 where
 	DB: MutKVStore + KVStore,
 {
-	let Some(existing) = ndb.save_non_overwririting_one_node(saved_root_node)? else {
-		return Ok(());
+	let mut batch = WriteBatch::new();
+
+	let Some(existing) = ndb.stage_non_overwriting_one_node(&mut batch, saved_root_node)? else {
+		return ndb.commit(batch).map_err(From::from);
 	};
 
 	match (saved_root_node, existing) {
-		(
-			SavedNode::Inner(root),
-			FetchedNode::Deserialized(DeserializedNode::Inner(deserialized_drafted, hash)),
-		) => {
-			if root.hash() != &hash {
-				return Err(MutableTreeErrorKind::ConflictingRoot);
-			}
-
-			let deserialized_hashed = deserialized_drafted.to_hashed(*root.version())?;
-
-			root.hash()
-				.eq(deserialized_hashed.hash())
-				.then_some(())
-				.ok_or(MutableTreeErrorKind::ConflictingRoot)
+		(SavedNode::Inner(root), FetchedNode::Deserialized(DeserializedNode::Inner(_, hash))) => {
+			root.hash().eq(&hash).then_some(()).ok_or(MutableTreeErrorKind::ConflictingRoot)
 		},
 		(
 			SavedNode::Leaf(root),
@@ -508,27 +1156,36 @@ where
 	Ok((inner.into(), updated))
 }
 
-// TODO: make this efficient by tracking the exact reference count,
-// `Arc::into_inner` should work with root.
-fn recursive_make_saved_nodes<DB>(
+/// Makes the saved counterpart of `drafted`, staging every newly saved node.
+///
+/// Drafted children are replaced in `drafted` only, leaving the nodes shared with the tree
+/// untouched until the staged version is committed.
+fn recursive_make_saved_nodes<F>(
 	drafted: DraftedNode,
-	ndb: &NodeDb<DB>,
+	batch: &mut WriteBatch,
 	version: U63,
 	nonce: &mut U31,
+	stage_node: &mut F,
 ) -> Result<SavedNode, MutableTreeErrorKind>
 where
-	DB: MutKVStore + KVStore,
+	F: FnMut(&mut WriteBatch, &SavedNode) -> Result<(), MutableTreeErrorKind>,
 {
 	*nonce = nonce.get().checked_add(1).and_then(U31::new).ok_or(MutableTreeErrorKind::Overflow)?;
 
 	let this_nonce = *nonce;
 
-	let mut save_arlock_node = |node: &mut ArlockNode| -> Result<_, MutableTreeErrorKind> {
-		let mut gnode_mut = node.write()?;
+	let mut save_child = |child: &mut Child| -> Result<_, MutableTreeErrorKind> {
+		let Child::Full(full) = child else {
+			return Ok(());
+		};
 
-		if let Node::Drafted(drafted) = gnode_mut.deref() {
-			*gnode_mut = recursive_make_saved_nodes(drafted.into(), ndb, version, nonce)?.into();
-		}
+		let drafted = match full.read()?.deref() {
+			Node::Drafted(drafted) => drafted.into(),
+			Node::Saved(_) => return Ok(()),
+		};
+
+		let saved = recursive_make_saved_nodes(drafted, batch, version, nonce, stage_node)?;
+		*child = Child::Full(saved.into());
 
 		Ok(())
 	};
@@ -536,23 +1193,15 @@ where
 	let saved = match drafted {
 		DraftedNode::Leaf(leaf) => leaf.to_hashed(version).into_saved(this_nonce).into(),
 		DraftedNode::Inner(mut inner) => {
-			match inner.left_mut() {
-				Child::Full(full) => save_arlock_node(full)?,
-				Child::Part(_) => (),
-			}
-
-			match inner.right_mut() {
-				Child::Full(full) => save_arlock_node(full)?,
-				Child::Part(_) => (),
-			}
+			save_child(inner.left_mut())?;
+			save_child(inner.right_mut())?;
 
 			// unwraps are safe because children must have been saved
 			inner.to_hashed(version).unwrap().into_saved(this_nonce).unwrap().into()
 		},
 	};
 
-	// TODO: remove this assert after save behaviour fully controlled
-	assert!(ndb.save_non_overwririting_one_node(&saved)?.is_none());
+	stage_node(batch, &saved)?;
 
 	Ok(saved)
 }
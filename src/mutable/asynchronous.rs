@@ -0,0 +1,290 @@
+use core::ops::Deref;
+
+use bytes::Bytes;
+use nebz::NonEmptyBz;
+use oblux::U63;
+
+use crate::{
+	GetError, NodeKey,
+	kvstore::{AsyncKVIterator, AsyncKVStore, AsyncMutKVStore, KVStore, WriteBatch},
+	node::{
+		self, ArlockNode, Child, Node, NodeError, SavedNode,
+		ndb::{FetchedNode, NodeDb},
+	},
+};
+
+use super::{
+	MutableTree,
+	error::{MutableTreeErrorKind, Result},
+	recursive_insert, recursive_remove,
+};
+
+/// Stands in for the store once the nodes an operation reaches are fetched ahead, failing any
+/// further read rather than blocking on it.
+#[derive(Debug, Clone, Copy)]
+struct Prefetched;
+
+#[derive(Debug, thiserror::Error)]
+#[error("unfetched node error: node must be fetched ahead")]
+struct UnfetchedNode;
+
+impl KVStore for Prefetched {
+	type Error = UnfetchedNode;
+
+	type Snapshot = Prefetched;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		Ok(Self)
+	}
+
+	fn get<K>(&self, _key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		Err(UnfetchedNode)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: AsyncMutKVStore + AsyncKVStore + AsyncKVIterator + Clone,
+{
+	/// Asynchronous counterpart of [`MutableTree::load_latest_version`].
+	pub async fn load_latest_version_async(db: DB) -> Result<Self> {
+		let tree = Self::load_latest_version_read_only_async(db).await?;
+
+		tree.ndb
+			.remove_versions_after_async(tree.version)
+			.await
+			.map_err(MutableTreeErrorKind::from)?;
+
+		Ok(tree)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: AsyncKVStore + AsyncKVIterator + Clone,
+{
+	/// Asynchronous counterpart of [`MutableTree::load_latest_version_read_only`].
+	pub async fn load_latest_version_read_only_async(db: DB) -> Result<Self> {
+		let ndb = NodeDb::builder().db(db).build();
+
+		let Some((latest_root_nk, latest_root_node)) =
+			ndb.fetch_latest_root_node_async().await.map_err(MutableTreeErrorKind::from)?
+		else {
+			return Ok(Self::with_ndb(ndb));
+		};
+
+		let root = resolve_root_node_async(&ndb, &latest_root_nk, latest_root_node).await?;
+
+		Self::with_latest_root(ndb, *latest_root_nk.version(), root).map_err(From::from)
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: AsyncMutKVStore + AsyncKVStore + Clone,
+{
+	/// Asynchronous counterpart of [`MutableTree::insert`].
+	///
+	/// The nodes on the path to `key` are fetched ahead, so that inserting does no I/O.
+	pub async fn insert_async(&mut self, key: NonEmptyBz<Bytes>, value: Bytes) -> Result<bool> {
+		let Some(root) = self.root.clone() else {
+			self.insert_into_empty(key, value);
+			return Ok(false);
+		};
+
+		let root = fetch_path(&root, &self.ndb, key.as_ref_slice(), false).await?;
+
+		let ndb = NodeDb::builder().db(Prefetched).build();
+		let (new_root, updated) = recursive_insert(&root, &ndb, key, value)?;
+
+		self.apply_insert(new_root, updated).map_err(From::from)
+	}
+
+	/// Asynchronous counterpart of [`MutableTree::remove`].
+	///
+	/// The nodes on the path to `key` are fetched ahead along with the ones rebalancing may
+	/// rotate, so that removing does no I/O.
+	pub async fn remove_async<K>(&mut self, key: NonEmptyBz<K>) -> Result<bool>
+	where
+		K: AsRef<[u8]>,
+	{
+		let Some(root) = self.root.clone() else {
+			return Ok(false);
+		};
+
+		let root = fetch_path(&root, &self.ndb, key.as_ref_slice(), true).await?;
+
+		let ndb = NodeDb::builder().db(Prefetched).build();
+		let (new_root, removed) = recursive_remove(root, &ndb, key)?;
+
+		Ok(self.apply_remove(new_root, removed))
+	}
+
+	/// Asynchronous counterpart of [`MutableTree::save`].
+	pub async fn save_async(&mut self) -> Result<U63> {
+		self.save_version_async(None).await
+	}
+
+	/// Asynchronous counterpart of [`MutableTree::save_with_metadata`].
+	pub async fn save_with_metadata_async<M>(&mut self, metadata: NonEmptyBz<M>) -> Result<U63>
+	where
+		M: AsRef<[u8]>,
+	{
+		self.save_version_async(Some(metadata.as_ref_slice())).await
+	}
+
+	/// Asynchronous counterpart of [`MutableTree::save_version`].
+	async fn save_version_async(&mut self, metadata: Option<NonEmptyBz<&[u8]>>) -> Result<U63> {
+		let mut batch = WriteBatch::new();
+		let mut staged = vec![];
+
+		// staging cannot wait on the store, hence the staged nodes are checked against the saved
+		// ones afterwards
+		let (version, saved) = self.stage_version(&mut batch, metadata, |batch, saved| {
+			staged.push(saved.node_key());
+			self.ndb.stage_one_node(batch, saved).map_err(From::from)
+		})?;
+
+		for nk in staged {
+			if self
+				.ndb
+				.fetch_one_node_async(&nk)
+				.await
+				.map_err(MutableTreeErrorKind::from)?
+				.is_some()
+			{
+				return Err(MutableTreeErrorKind::ConflictingNode(*nk.version()).into());
+			}
+		}
+
+		self.ndb.commit_async(batch).await.map_err(MutableTreeErrorKind::from)?;
+
+		Ok(self.apply_saved_version(version, saved))
+	}
+}
+
+impl<DB> MutableTree<DB>
+where
+	DB: AsyncKVStore,
+{
+	/// Asynchronous counterpart of [`Get::get`](crate::Get::get).
+	pub async fn get_async<K>(&self, key: NonEmptyBz<K>) -> Result<(U63, Option<Bytes>), GetError>
+	where
+		K: AsRef<[u8]>,
+	{
+		let Some(root) = self.root() else {
+			return Ok((U63::MIN, None));
+		};
+
+		let ndb = self.ndb.snapshot_async().await.map_err(NodeError::from)?;
+
+		node::get_async(root.clone(), &ndb, key).await.map_err(From::from)
+	}
+}
+
+/// Asynchronous counterpart of [`resolve_root_node`](super::resolve_root_node).
+async fn resolve_root_node_async<DB>(
+	ndb: &NodeDb<DB>,
+	root_nk: &NodeKey,
+	root_node: FetchedNode,
+) -> Result<Option<SavedNode>, MutableTreeErrorKind>
+where
+	DB: AsyncKVStore,
+{
+	let root = match root_node {
+		FetchedNode::EmptyRoot => return Ok(None),
+		FetchedNode::Deserialized(denode) => denode.into_saved(root_nk)?,
+		FetchedNode::ReferenceRoot(nk) => match ndb.fetch_one_node_async(&nk).await? {
+			Some(FetchedNode::Deserialized(denode)) => denode.into_saved(&nk)?,
+			Some(_) => return Err(MutableTreeErrorKind::ConflictingRoot),
+			None => return Ok(None),
+		},
+	};
+
+	Ok(Some(root))
+}
+
+/// Fetches the nodes on the path to `key` along with their siblings, returning the root they
+/// are fetched into.
+///
+/// Saved nodes are shared with the last saved tree, hence the ones fetched into are detached
+/// copies, see [`detach`], so that the saved tree does not keep every fetched node.
+///
+/// With `rotations`, the two levels below each sibling are fetched as well, which rebalancing
+/// reaches into once the sibling outweighs the path.
+async fn fetch_path<DB>(
+	root: &ArlockNode,
+	ndb: &NodeDb<DB>,
+	key: NonEmptyBz<&[u8]>,
+	rotations: bool,
+) -> Result<ArlockNode, MutableTreeErrorKind>
+where
+	DB: AsyncKVStore,
+{
+	let root = detach(root)?;
+	let mut node = root.clone();
+
+	while let Some((left, right)) = fetch_children(&node, ndb).await? {
+		let goes_left = key < node.read()?.key().as_ref_slice();
+		let (next, sibling) = if goes_left {
+			(left, right)
+		} else {
+			(right, left)
+		};
+
+		if rotations && let Some((outer, inner)) = fetch_children(&sibling, ndb).await? {
+			fetch_children(&outer, ndb).await?;
+			fetch_children(&inner, ndb).await?;
+		}
+
+		node = next;
+	}
+
+	Ok(root)
+}
+
+/// Fetches the children of `node` into it as [detached](detach) nodes, returning them unless
+/// `node` is a leaf.
+///
+/// `node` must be detached itself.
+async fn fetch_children<DB>(
+	node: &ArlockNode,
+	ndb: &NodeDb<DB>,
+) -> Result<Option<(ArlockNode, ArlockNode)>, MutableTreeErrorKind>
+where
+	DB: AsyncKVStore,
+{
+	let (left, right) = {
+		let gnode = node.read()?;
+
+		match (gnode.left(), gnode.right()) {
+			(Some(left), Some(right)) => (left.clone(), right.clone()),
+			_ => return Ok(None),
+		}
+	};
+
+	let left = detach(&left.fetch_full_async(ndb).await?)?;
+	let right = detach(&right.fetch_full_async(ndb).await?)?;
+
+	let mut gnode_mut = node.write()?;
+
+	// unwraps are safe because node is inner node
+	*gnode_mut.left_mut().unwrap() = Child::Full(left.clone());
+	*gnode_mut.right_mut().unwrap() = Child::Full(right.clone());
+
+	Ok(Some((left, right)))
+}
+
+/// Returns a copy of `node` if it is saved, or `node` itself if drafted, as drafted nodes belong
+/// to the working tree only.
+fn detach(node: &ArlockNode) -> Result<ArlockNode, MutableTreeErrorKind> {
+	let detached = match node.read()?.deref() {
+		Node::Saved(saved) => Node::Saved(saved.clone()).into(),
+		Node::Drafted(_) => node.clone(),
+	};
+
+	Ok(detached)
+}
@@ -1,5 +1,7 @@
 use std::sync::PoisonError;
 
+use oblux::U63;
+
 use crate::node::{InnerNodeError, NodeError, ndb::NodeDbError};
 
 pub type Result<T, E = MutableTreeError> = core::result::Result<T, E>;
@@ -19,9 +21,24 @@ pub(crate) enum MutableTreeErrorKind {
 	#[error("missing node key error")]
 	MissingNodeKey,
 
+	#[error("missing version error: version {} does not exist", .0.get())]
+	MissingVersion(U63),
+
+	#[error("conflicting checkpoint error: tag already marks another version")]
+	ConflictingCheckpoint,
+
 	#[error("conflicting root error")]
 	ConflictingRoot,
 
+	#[error("conflicting node error: version {} already has nodes saved", .0.get())]
+	ConflictingNode(U63),
+
+	#[error("commit mismatch error: root of version {} differs from its commit marker", .0.get())]
+	CommitMismatch(U63),
+
+	#[error("copy mismatch error: version {} must have the same root in both stores", .0.get())]
+	CopyMismatch(U63),
+
 	#[error("inner node error: {0}")]
 	InnerNode(#[from] InnerNodeError),
 
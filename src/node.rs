@@ -19,7 +19,10 @@ pub(crate) use self::{
 
 use std::sync::{Arc, RwLock};
 
-use super::{NodeHash, NodeKey, kvstore::KVStore};
+use super::{
+	NodeHash, NodeKey,
+	kvstore::{AsyncKVStore, KVStore},
+};
 
 use self::{error::Result, ndb::NodeDb};
 
@@ -115,6 +118,21 @@ impl Node {
 		ndb: &NodeDb<DB>,
 		key: NonEmptyBz<K>,
 	) -> Result<(U63, Option<Bytes>), NodeError>
+	where
+		K: AsRef<[u8]>,
+		DB: KVStore,
+	{
+		self.get_leaf(ndb, key).map(|(i, leaf)| (i, leaf.map(|(value, _)| value)))
+	}
+
+	/// Gets the index and value of `key` along with the [`NodeKey`] of its leaf,
+	/// which is [`None`] while the leaf is not saved yet.
+	#[allow(clippy::type_complexity)]
+	pub fn get_leaf<DB, K>(
+		&self,
+		ndb: &NodeDb<DB>,
+		key: NonEmptyBz<K>,
+	) -> Result<(U63, Option<(Bytes, Option<NodeKey>)>), NodeError>
 	where
 		K: AsRef<[u8]>,
 		DB: KVStore,
@@ -122,7 +140,8 @@ impl Node {
 		// leaf node check
 		if let Some(value) = self.value() {
 			if key.as_ref_slice() == self.key().as_ref_slice() {
-				return Ok((U63::MIN, Some(value.clone())));
+				let nk = self.as_saved().map(SavedNode::node_key);
+				return Ok((U63::MIN, Some((value.clone(), nk))));
 			}
 
 			return Ok((U63::MIN, None));
@@ -136,7 +155,7 @@ impl Node {
 				.transpose()?
 				.unwrap()
 				.read()?
-				.get(ndb, key);
+				.get_leaf(ndb, key);
 		}
 
 		// unwrap is safe because self is inner node
@@ -144,17 +163,62 @@ impl Node {
 		let right = right.read()?;
 		let right_size = right.size().get();
 
-		right.get(ndb, key).map(|(i, v)| {
+		right.get_leaf(ndb, key).map(|(i, leaf)| {
 			(
 				// TODO: ascertain whether the index can exceed `U63` bounds.
 				// direct subtraction is safe because parent's size always exceeds that of the child
 				i.get().checked_add(self.size().get() - right_size).and_then(U63::new).unwrap(),
-				v,
+				leaf,
 			)
 		})
 	}
 }
 
+/// Asynchronous counterpart of [`Node::get`], fetching the nodes down to `key` from `ndb`
+/// without blocking.
+pub async fn get_async<DB, K>(
+	mut node: ArlockNode,
+	ndb: &NodeDb<DB>,
+	key: NonEmptyBz<K>,
+) -> Result<(U63, Option<Bytes>), NodeError>
+where
+	K: AsRef<[u8]>,
+	DB: AsyncKVStore,
+{
+	let mut idx = U63::MIN.get();
+
+	loop {
+		// the guard must not be held across awaits, hence the child is cloned out of it
+		let (child, parent_size) = {
+			let gnode = node.read()?;
+
+			if let Some(value) = gnode.value() {
+				let value =
+					(key.as_ref_slice() == gnode.key().as_ref_slice()).then(|| value.clone());
+
+				// unwrap is safe because the index is bounded by the size of the tree
+				return Ok((U63::new(idx).unwrap(), value));
+			}
+
+			// unwraps are safe because gnode is inner node
+			if key.as_ref_slice() < gnode.key().as_ref_slice() {
+				(gnode.left().cloned().unwrap(), None)
+			} else {
+				(gnode.right().cloned().unwrap(), Some(gnode.size()))
+			}
+		};
+
+		let child = child.fetch_full_async(ndb).await?;
+
+		if let Some(parent_size) = parent_size {
+			// direct subtraction is safe because parent's size always exceeds that of the child
+			idx += parent_size.get() - child.read()?.size().get();
+		}
+
+		node = child;
+	}
+}
+
 impl From<Node> for ArlockNode {
 	fn from(node: Node) -> Self {
 		Arc::new(RwLock::new(node))
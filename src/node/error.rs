@@ -1,6 +1,6 @@
 use std::{borrow::Cow, sync::PoisonError};
 
-use super::inner::InnerNodeError;
+use super::{inner::InnerNodeError, ndb::NodeDbError};
 
 pub type Result<T, E = NodeError> = core::result::Result<T, E>;
 
@@ -12,6 +12,9 @@ pub enum NodeError {
 	#[error("inner node error: {0}")]
 	Inner(#[from] InnerNodeError),
 
+	#[error("node db error: {0}")]
+	NodeDb(#[from] NodeDbError),
+
 	#[error("deserialization error: {0}")]
 	Deserialization(#[from] crate::encoding::DeserializationError),
 
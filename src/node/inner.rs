@@ -17,13 +17,13 @@ use oblux::{U7, U63};
 use sha2::{Digest, Sha256};
 
 use crate::{
-	NodeHashPair, NodeKey, NodeKeyPair,
+	NodeKey, NodeKeyPair,
 	encoding::{self, SerializationError},
-	kvstore::KVStore,
+	kvstore::{AsyncKVStore, KVStore},
 };
 
 use super::{
-	ArlockNode, Node, SavedNode,
+	ArlockNode, Node, NodeHash, SavedNode,
 	info::{Drafted, Drafter, Hashed, Hasher, Saved, Saver},
 	ndb::{FetchedNode, NodeDb},
 };
@@ -82,7 +82,7 @@ impl InnerNode<Drafted> {
 }
 
 impl InnerNode<Drafted> {
-	pub fn to_hashed(&self, version: U63) -> Result<InnerNode<Hashed<NodeHashPair>>> {
+	pub fn to_hashed(&self, version: U63) -> Result<InnerNode<Hashed>> {
 		let left = self
 			.left()
 			.as_full()
@@ -95,44 +95,51 @@ impl InnerNode<Drafted> {
 			.ok_or("inner node's children must be hashed".into())
 			.map_err(InnerNodeError::IntoHashed)?;
 
-		let (hash, left_hash, right_hash) = {
-			let mut hasher = Sha256::new();
-
-			// unwrap calls are safe because write on Sha256's hasher is infallible
-			hasher.write_varint(self.height.to_signed()).unwrap();
-			hasher.write_varint(self.size.to_signed()).unwrap();
-			hasher.write_varint(version.to_signed()).unwrap();
-
-			let left_hash = *left
+		let child_hash = |child: &ArlockNode| {
+			child
 				.read()?
 				.hash()
-				.inspect(|&h| {
-					encoding::serialize_hash(h, &mut hasher).unwrap();
-				})
+				.copied()
 				.ok_or("inner node's children must be hashed".into())
-				.map_err(InnerNodeError::IntoHashed)?;
+				.map_err(InnerNodeError::IntoHashed)
+		};
 
-			let right_hash = *right
-				.read()?
-				.hash()
-				.inspect(|&h| {
-					encoding::serialize_hash(h, &mut hasher).unwrap();
-				})
-				.ok_or("inner node's children must be hashed".into())
-				.map_err(InnerNodeError::IntoHashed)?;
+		let hash = self.hash_with_children(version, &child_hash(left)?, &child_hash(right)?);
 
-			(hasher.finalize(), left_hash, right_hash)
-		};
+		Ok(self.clone().into_hashed_unchecked(version, hash))
+	}
 
-		let inner_node = InnerNode {
-			info: self.info.clone().into_hashed(version, hash.into(), (left_hash, right_hash)),
+	/// Computes the hash of the node as saved in `version`, given the hashes of its children.
+	pub fn hash_with_children(
+		&self,
+		version: U63,
+		left_hash: &NodeHash,
+		right_hash: &NodeHash,
+	) -> NodeHash {
+		let mut hasher = Sha256::new();
+
+		// unwrap calls are safe because write on Sha256's hasher is infallible
+		hasher.write_varint(self.height.to_signed()).unwrap();
+		hasher.write_varint(self.size.to_signed()).unwrap();
+		hasher.write_varint(version.to_signed()).unwrap();
+
+		encoding::serialize_hash(left_hash, &mut hasher).unwrap();
+		encoding::serialize_hash(right_hash, &mut hasher).unwrap();
+
+		hasher.finalize().into()
+	}
+
+	/// Hashes the node with the given `hash` without recomputing it from the children.
+	///
+	/// Meant for nodes whose children are not loaded, e.g. deserialized ones.
+	pub fn into_hashed_unchecked(self, version: U63, hash: NodeHash) -> InnerNode<Hashed> {
+		InnerNode {
+			info: self.info.into_hashed(version, hash, ()),
 			height: self.height,
 			size: self.size,
-			left: self.left.clone(),
-			right: self.right.clone(),
-		};
-
-		Ok(inner_node)
+			left: self.left,
+			right: self.right,
+		}
 	}
 }
 
@@ -244,7 +251,7 @@ impl<K, VERSION, HASH, HAUX, NONCE>
 }
 
 impl Child {
-	pub fn node_key(&self) -> Result<Option<NodeKey>, PoisonError<RwLockReadGuard<Node>>> {
+	pub fn node_key(&self) -> Result<Option<NodeKey>, PoisonError<RwLockReadGuard<'_, Node>>> {
 		match self {
 			Self::Full(node) => Ok(node.read()?.as_saved().map(SavedNode::node_key)),
 			Self::Part(nk) => Ok(Some(nk.clone())),
@@ -258,6 +265,8 @@ impl Child {
 		}
 	}
 
+	/// Returns the child node, fetching it if only its [`NodeKey`] is known, from the node cache
+	/// of `ndb` first.
 	pub fn fetch_full<DB>(&self, ndb: &NodeDb<DB>) -> Result<ArlockNode>
 	where
 		DB: KVStore,
@@ -267,16 +276,34 @@ impl Child {
 			Child::Part(nk) => nk,
 		};
 
-		ndb.fetch_one_node(nk)?
-			.map(|node| match node {
-				FetchedNode::Deserialized(denode) => denode.into_saved_checked(nk),
-				FetchedNode::EmptyRoot | FetchedNode::ReferenceRoot(_) => {
-					Err(InnerNodeError::InvalidChild)
-				},
-			})
-			.transpose()?
-			.map(From::from)
-			.ok_or(InnerNodeError::ChildNotFound)
+		if let Some(cached) = ndb.cached_node(nk)? {
+			return Ok(cached.into());
+		}
+
+		let node = make_child_node(nk, ndb.fetch_one_node(nk)?)?;
+		ndb.cache_node(&node)?;
+
+		Ok(node.into())
+	}
+
+	/// Asynchronous counterpart of [`Child::fetch_full`].
+	pub async fn fetch_full_async<DB>(&self, ndb: &NodeDb<DB>) -> Result<ArlockNode>
+	where
+		DB: AsyncKVStore,
+	{
+		let nk = match self {
+			Child::Full(full) => return Ok(full.clone()),
+			Child::Part(nk) => nk,
+		};
+
+		if let Some(cached) = ndb.cached_node(nk)? {
+			return Ok(cached.into());
+		}
+
+		let node = make_child_node(nk, ndb.fetch_one_node_async(nk).await?)?;
+		ndb.cache_node(&node)?;
+
+		Ok(node.into())
 	}
 
 	pub fn extract(&mut self) -> Result<Self> {
@@ -316,3 +343,15 @@ impl From<ArlockNode> for Child {
 		Self::Full(node)
 	}
 }
+
+fn make_child_node(nk: &NodeKey, fetched: Option<FetchedNode>) -> Result<SavedNode> {
+	fetched
+		.map(|node| match node {
+			FetchedNode::Deserialized(denode) => denode.into_saved(nk),
+			FetchedNode::EmptyRoot | FetchedNode::ReferenceRoot(_) => {
+				Err(InnerNodeError::InvalidChild)
+			},
+		})
+		.transpose()?
+		.ok_or(InnerNodeError::ChildNotFound)
+}
@@ -4,11 +4,7 @@ use oblux::{U7, U63};
 
 use crate::{
 	kvstore::KVStore,
-	node::{
-		ArlockNode, DraftedNode,
-		info::Drafted,
-		ndb::{FetchedNode, NodeDb},
-	},
+	node::{ArlockNode, DraftedNode, info::Drafted, ndb::NodeDb},
 };
 
 use super::{Child, InnerNode, InnerNodeError, Result};
@@ -20,24 +16,7 @@ impl InnerNode<Drafted> {
 	where
 		DB: KVStore,
 	{
-		let extract_full = |child: &mut Child| -> Result<_> {
-			let node = match child.extract()? {
-				Child::Full(full) => full,
-				Child::Part(nk) => ndb
-					.fetch_one_node(&nk)?
-					.map(|node| match node {
-						FetchedNode::Deserialized(denode) => denode.into_saved_checked(&nk),
-						FetchedNode::EmptyRoot | FetchedNode::ReferenceRoot(_) => {
-							Err(InnerNodeError::InvalidChild)
-						},
-					})
-					.transpose()?
-					.map(From::from)
-					.ok_or(InnerNodeError::ChildNotFound)?,
-			};
-
-			Ok(node)
-		};
+		let extract_full = |child: &mut Child| child.extract()?.fetch_full(ndb);
 
 		let height_size_pair = |node: &ArlockNode| -> Result<_> {
 			node.read().map(|gnode| (gnode.height(), gnode.size())).map_err(From::from)
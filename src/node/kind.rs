@@ -8,7 +8,7 @@ use nebz::NonEmptyBz;
 use oblux::{U7, U63};
 
 use crate::{
-	NodeHashPair, NodeKey, NodeKeyPair,
+	NodeKey, NodeKeyPair,
 	encoding::{self, DeserializationError, SerializationError},
 };
 
@@ -26,7 +26,7 @@ pub(crate) enum DraftedNode {
 
 #[derive(Debug, Clone)]
 pub(crate) enum SavedNode {
-	Inner(InnerNode<Saved<NodeHashPair, NodeKeyPair>>),
+	Inner(InnerNode<Saved<(), NodeKeyPair>>),
 	Leaf(LeafNode<Saved>),
 }
 
@@ -79,17 +79,26 @@ impl DeserializedNode {
 		Ok(Self::Inner(inner_node, node_hash))
 	}
 
-	pub fn into_saved_checked(self, nk: &NodeKey) -> Result<SavedNode, InnerNodeError> {
+	/// Returns the hash of the node as saved in `version`.
+	///
+	/// Inner nodes carry their hash, whereas leaf nodes are hashed.
+	pub fn hash(&self, version: U63) -> NodeHash {
 		match self {
-			DeserializedNode::Inner(inner, hash) => {
-				let hashed = inner.to_hashed(*nk.version())?;
-
-				if hashed.hash() != &hash {
-					return Err(InnerNodeError::Other("inconsistent hash".into()));
-				}
+			Self::Inner(_, hash) => *hash,
+			Self::Leaf(leaf) => *leaf.to_hashed(version).hash(),
+		}
+	}
 
-				hashed.into_saved(*nk.nonce()).map(SavedNode::Inner)
-			},
+	/// Turns the node into a saved one under `nk`.
+	///
+	/// Nothing is verified: inner nodes keep their stored hash since their children are not
+	/// fetched, and leaf nodes are hashed from their contents.
+	pub fn into_saved(self, nk: &NodeKey) -> Result<SavedNode, InnerNodeError> {
+		match self {
+			DeserializedNode::Inner(inner, hash) => inner
+				.into_hashed_unchecked(*nk.version(), hash)
+				.into_saved(*nk.nonce())
+				.map(SavedNode::Inner),
 			DeserializedNode::Leaf(leaf) => {
 				let saved_leaf = leaf.to_hashed(*nk.version()).into_saved(*nk.nonce());
 
@@ -271,8 +280,8 @@ impl From<&Node> for DraftedNode {
 	}
 }
 
-impl From<InnerNode<Saved<NodeHashPair, NodeKeyPair>>> for SavedNode {
-	fn from(node: InnerNode<Saved<NodeHashPair, NodeKeyPair>>) -> Self {
+impl From<InnerNode<Saved<(), NodeKeyPair>>> for SavedNode {
+	fn from(node: InnerNode<Saved<(), NodeKeyPair>>) -> Self {
 		Self::Inner(node)
 	}
 }
@@ -313,8 +322,8 @@ impl From<LeafNode<Saved>> for Node {
 	}
 }
 
-impl From<InnerNode<Saved<NodeHashPair, NodeKeyPair>>> for Node {
-	fn from(node: InnerNode<Saved<NodeHashPair, NodeKeyPair>>) -> Self {
+impl From<InnerNode<Saved<(), NodeKeyPair>>> for Node {
+	fn from(node: InnerNode<Saved<(), NodeKeyPair>>) -> Self {
 		SavedNode::from(node).into()
 	}
 }
@@ -349,8 +358,8 @@ impl From<LeafNode<Saved>> for ArlockNode {
 	}
 }
 
-impl From<InnerNode<Saved<NodeHashPair, NodeKeyPair>>> for ArlockNode {
-	fn from(node: InnerNode<Saved<NodeHashPair, NodeKeyPair>>) -> Self {
+impl From<InnerNode<Saved<(), NodeKeyPair>>> for ArlockNode {
+	fn from(node: InnerNode<Saved<(), NodeKeyPair>>) -> Self {
 		Node::from(node).into()
 	}
 }
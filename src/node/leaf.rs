@@ -41,6 +41,11 @@ impl<INFO, V> LeafNode<INFO, V> {
 	pub fn value(&self) -> &V {
 		&self.value
 	}
+
+	/// Returns the leaf with its value replaced by `value`.
+	pub fn with_value<W>(self, value: W) -> LeafNode<INFO, W> {
+		LeafNode { info: self.info, value }
+	}
 }
 
 #[bon::bon]
@@ -1,26 +1,71 @@
+mod cache;
+mod checksum;
+mod compression;
 mod error;
 
-pub use self::error::NodeDbError;
+pub use self::{cache::NodeCacheStats, compression::Compression, error::NodeDbError};
+
+use core::{mem, num::NonZeroUsize};
+
+use std::sync::{Arc, Mutex};
 
 use bon::Builder;
-use bytes::{Buf, BufMut, BytesMut};
+use bytes::{Buf, BufMut, Bytes, BytesMut};
 use nebz::NonEmptyBz;
 use oblux::{U31, U63};
+use sha2::{Digest, Sha256};
 
 use crate::{
+	NodeHash,
 	encoding::{self, DeserializationError, NODE_DB_KEY_LEN},
-	kvstore::{KVIterator, KVStore, MutKVStore},
+	kvstore::{
+		AsyncKVIterator, AsyncKVStore, AsyncMutKVStore, KVIterator, KVStore, MutKVStore, WriteBatch,
+	},
 };
 
-use super::{DeserializedNode, NodeKey, kind::SavedNode};
+use super::{DeserializedNode, LeafNode, NodeKey, info::Drafted, kind::SavedNode};
 
-use self::error::Result;
+use self::{cache::NodeCache, error::Result};
 
 const NODE_DB_KEY_PREFIX: u8 = b's';
 
+const COMMIT_MARKER_KEY_PREFIX: u8 = b'c';
+
+const CHECKPOINT_KEY_PREFIX: u8 = b't';
+
+const VERSION_METADATA_KEY_PREFIX: u8 = b'v';
+
+const VALUE_KEY_PREFIX: u8 = b'b';
+
+/// Start and end of the commit marker keys.
+const COMMIT_MARKER_KEY_BOUNDS: ([u8; 1], [u8; 1]) =
+	([COMMIT_MARKER_KEY_PREFIX], [COMMIT_MARKER_KEY_PREFIX + 1]);
+
+// like compression tags, it exceeds the first byte of any serialized node
+const SEPARATED_VALUE_TAG: u8 = 0xFA;
+
+/// Number of entries [`NodeDb::copy_into`] writes at a time.
+const COPY_BATCH_LEN: usize = 1024;
+
 #[derive(Debug, Clone, Builder)]
 pub(crate) struct NodeDb<DB> {
 	db: DB,
+
+	/// Compression of the nodes staged from now on; nodes are read back regardless of it.
+	compression: Option<Compression>,
+
+	/// Whether the nodes staged from now on carry a checksum, which is verified on fetching.
+	#[builder(default)]
+	checksums: bool,
+
+	/// Minimum length of the values staged out of line from now on, see
+	/// [`NodeDb::stage_one_node`].
+	separated_value_min_len: Option<NonZeroUsize>,
+
+	/// Cache of the saved nodes fetched through [`NodeDb::cached_node`] misses, shared with the
+	/// snapshots of the node db.
+	#[builder(skip)]
+	cache: Option<Arc<Mutex<NodeCache>>>,
 }
 
 pub(crate) enum FetchedNode {
@@ -29,44 +74,130 @@ pub(crate) enum FetchedNode {
 	Deserialized(DeserializedNode),
 }
 
+/// A node as stored, whose leaf may still reference its value by hash.
+enum StoredNode {
+	Fetched(FetchedNode),
+	SeparatedLeaf(LeafNode<Drafted>, NodeHash),
+}
+
+/// Search for the latest version having a root, walking the node keys down from the end.
+struct UnmarkedRootSearch {
+	end: [u8; NODE_DB_KEY_LEN],
+}
+
 impl<DB> NodeDb<DB> {
 	// the serialized bytes of a node cannot start with byte value `0xFF` as it exceeds U7::MAX
 	const EMPTY_ROOT_MARKER: u8 = u8::MAX;
 
 	const NEW_ROOT_NONCE: U31 = U31::ONE;
-}
 
-impl<DB> NodeDb<DB>
-where
-	DB: KVStore,
-{
-	pub fn fetch_one_node(&self, nk: &NodeKey) -> Result<Option<FetchedNode>> {
-		let ndb_key = encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(nk);
+	pub fn set_compression(&mut self, compression: Option<Compression>) {
+		self.compression = compression;
+	}
+
+	pub fn set_checksums(&mut self, checksums: bool) {
+		self.checksums = checksums;
+	}
+
+	pub fn set_separated_value_min_len(&mut self, min_len: Option<NonZeroUsize>) {
+		self.separated_value_min_len = min_len;
+	}
+
+	/// Replaces the node cache with an empty one bounded by `max_bytes`, or drops it if `None`.
+	pub fn set_node_cache(&mut self, max_bytes: Option<NonZeroUsize>) {
+		self.cache = max_bytes.map(|max_bytes| Arc::new(Mutex::new(NodeCache::new(max_bytes))));
+	}
 
+	pub fn node_cache_stats(&self) -> Result<Option<NodeCacheStats>> {
+		self.cache.as_ref().map(|cache| Ok(cache.lock()?.stats())).transpose()
+	}
+
+	/// Returns the node cached against `nk`, if any node cache.
+	pub fn cached_node(&self, nk: &NodeKey) -> Result<Option<SavedNode>> {
+		match &self.cache {
+			Some(cache) => Ok(cache.lock()?.get(nk)),
+			None => Ok(None),
+		}
+	}
+
+	/// Caches `node` fetched from the store, if any node cache.
+	pub fn cache_node(&self, node: &SavedNode) -> Result<()> {
+		if let Some(cache) = &self.cache {
+			cache.lock()?.insert(node);
+		}
+
+		Ok(())
+	}
+
+	/// Evicts the cached nodes of every version after `version`, whose node keys are reused
+	/// once removed from the store.
+	fn evict_cached_versions_after(&self, version: U63) -> Result<()> {
+		if let Some(cache) = &self.cache {
+			cache.lock()?.evict_versions_after(version);
+		}
+
+		Ok(())
+	}
+
+	fn separates(&self, value: &[u8]) -> bool {
+		self.separated_value_min_len.is_some_and(|min_len| value.len() >= min_len.get())
+	}
+
+	pub fn into_db(self) -> DB {
 		self.db
-			.get(NonEmptyBz::from_owned_array(ndb_key))
-			.map_err(From::from)
-			.map_err(NodeDbError::Store)?
-			.map(make_fetched_node)
-			.transpose()
-			.map_err(From::from)
 	}
-}
 
-impl<DB> NodeDb<DB>
-where
-	DB: MutKVStore,
-{
-	/// Overwrites serialized bytes of `node` against `node`'s [`NodeKey`].
+	/// Returns a [`NodeDb`] over `db` configured like this one.
+	fn with_db<D>(&self, db: D) -> NodeDb<D> {
+		NodeDb {
+			db,
+			compression: self.compression,
+			checksums: self.checksums,
+			separated_value_min_len: self.separated_value_min_len,
+			cache: self.cache.clone(),
+		}
+	}
+
+	/// Stages serialized bytes of `node` against `node`'s [`NodeKey`] into `batch`.
 	///
-	/// Returns true if the same [`NodeKey`] of `node` already existed.
-	pub fn save_overwriting_one_node(&self, node: &SavedNode) -> Result<bool> {
+	/// A leaf's value reaching the separated value length is staged out of line against its
+	/// hash instead, which the leaf references. Rewriting the value stores it once all the same.
+	pub fn stage_one_node(&self, batch: &mut WriteBatch, node: &SavedNode) -> Result<()> {
 		let serialized = {
 			let mut serialized = BytesMut::new().writer();
 
-			node.serialize(&mut serialized)?;
+			match node {
+				SavedNode::Leaf(leaf) if self.separates(leaf.value()) => {
+					let value_hash: NodeHash = Sha256::digest(leaf.value()).into();
+
+					// unwrap is safe because separated values are non-empty
+					batch.insert(
+						NonEmptyBz::from_owned_array(value_key(&value_hash)),
+						NonEmptyBz::new(leaf.value().clone()).unwrap(),
+					);
+
+					serialized.get_mut().put_u8(SEPARATED_VALUE_TAG);
+					leaf.clone().with_value(value_hash).serialize(&mut serialized)?;
+				},
+				_ => {
+					node.serialize(&mut serialized)?;
+				},
+			}
+
+			let serialized = serialized.into_inner().freeze();
+
+			let serialized = match self.compression {
+				Some(compression) => compression::compress(compression, serialized)?,
+				None => serialized,
+			};
+
+			let serialized = if self.checksums {
+				checksum::append(serialized)
+			} else {
+				serialized
+			};
 
-			NonEmptyBz::new(serialized.into_inner().freeze())
+			NonEmptyBz::new(serialized)
 				.ok_or(NodeDbError::Other("serialized must be non-empty".into()))?
 		};
 
@@ -75,53 +206,195 @@ where
 			NonEmptyBz::from_owned_array(ndb_key_array)
 		};
 
-		self.db.insert(ndb_key, serialized).map_err(From::from).map_err(NodeDbError::Store)
+		batch.insert(ndb_key, serialized);
+
+		Ok(())
 	}
 
-	/// Overwrites empty root marker against [`NodeKey`] with `version` and nonce [`U31::ONE`].
-	///
-	/// Returns true if the same [`NodeKey`] with `version` and nonce [`U31::ONE`] already existed.
-	pub fn save_overwriting_empty_root(&self, version: U63) -> Result<bool> {
-		let ndb_key = root_ndb_key(version);
+	/// Stages empty root marker against [`NodeKey`] with `version` and nonce [`U31::ONE`] into
+	/// `batch`.
+	pub fn stage_empty_root(&self, batch: &mut WriteBatch, version: U63) {
 		let marker_value = NonEmptyBz::from_owned_array(Self::EMPTY_ROOT_MARKER.to_be_bytes());
 
-		self.db.insert(ndb_key, marker_value).map_err(From::from).map_err(NodeDbError::Store)
+		batch.insert(root_ndb_key(version), marker_value);
 	}
 
-	/// Overwrites original node-db key in node-db key format `s<version><nonce>`
-	/// against [`NodeKey`] with `version` and nonce [`U31::ONE`].
-	///
-	/// Returns true if the same [`NodeKey`] with `version` and nonce [`U31::ONE`] already existed.
-	pub fn save_overwriting_reference_root(
+	/// Stages original node-db key in node-db key format `s<version><nonce>`
+	/// against [`NodeKey`] with `version` and nonce [`U31::ONE`] into `batch`.
+	pub fn stage_reference_root(
 		&self,
+		batch: &mut WriteBatch,
 		version: U63,
 		original_nk: &NodeKey,
-	) -> Result<bool> {
+	) {
 		let original_root_ndb_key =
 			NonEmptyBz::from_owned_array(encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(original_nk));
 
+		batch.insert(root_ndb_key(version), original_root_ndb_key);
+	}
+
+	/// Stages the commit marker of `version`, holding its `root_hash`, into `batch`.
+	///
+	/// The marker must be staged last, so that backends without atomic batches write it only
+	/// once the rest of the version is written.
+	pub fn stage_commit_marker(&self, batch: &mut WriteBatch, version: U63, root_hash: &NodeHash) {
+		let key = encoding::make_version_key::<COMMIT_MARKER_KEY_PREFIX>(version);
+
+		batch.insert(
+			NonEmptyBz::from_owned_array(key),
+			NonEmptyBz::from_borrowed_array(root_hash),
+		);
+	}
+
+	/// Stages `metadata` against `version` into `batch`.
+	pub fn stage_version_metadata<M>(
+		&self,
+		batch: &mut WriteBatch,
+		version: U63,
+		metadata: NonEmptyBz<M>,
+	) where
+		M: AsRef<[u8]>,
+	{
+		let key = encoding::make_version_key::<VERSION_METADATA_KEY_PREFIX>(version);
+
+		batch.insert(NonEmptyBz::from_owned_array(key), metadata);
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: KVStore,
+{
+	/// Returns a [`NodeDb`] reading from a snapshot of the store, see [`KVStore::snapshot`].
+	pub fn snapshot(&self) -> Result<NodeDb<DB::Snapshot>> {
+		let db = self.db.snapshot().map_err(From::from).map_err(NodeDbError::Store)?;
+
+		Ok(self.with_db(db))
+	}
+
+	pub fn fetch_one_node(&self, nk: &NodeKey) -> Result<Option<FetchedNode>> {
+		let ndb_key = encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(nk);
+
+		let Some(stored) = self
+			.db
+			.get(NonEmptyBz::from_owned_array(ndb_key))
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.map(make_stored_node)
+			.transpose()?
+		else {
+			return Ok(None);
+		};
+
+		let value = match stored.value_key() {
+			Some(value_key) => self
+				.db
+				.get(NonEmptyBz::from_owned_array(value_key))
+				.map_err(From::from)
+				.map_err(NodeDbError::Store)?,
+			None => None,
+		};
+
+		stored.complete(value).map(Some)
+	}
+
+	/// Fetches the root entry of `version` i.e. the one against [`NodeKey`] with `version` and
+	/// nonce [`U31::ONE`].
+	///
+	/// Returns [`None`] if `version` was never saved.
+	pub fn fetch_root_node(&self, version: U63) -> Result<Option<(NodeKey, FetchedNode)>> {
+		let nk = NodeKey::new(version, Self::NEW_ROOT_NONCE);
+
+		self.fetch_one_node(&nk).map(|root| root.map(|root| (nk, root)))
+	}
+
+	/// Returns whether `version` carries a commit marker, i.e. was fully saved.
+	pub fn has_commit_marker(&self, version: U63) -> Result<bool> {
+		let key = encoding::make_version_key::<COMMIT_MARKER_KEY_PREFIX>(version);
+
+		self.db
+			.has(NonEmptyBz::from_owned_array(key))
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)
+	}
+
+	/// Fetches the metadata saved along with `version`, if any.
+	pub fn fetch_version_metadata(&self, version: U63) -> Result<Option<NonEmptyBz<Bytes>>> {
+		let key = encoding::make_version_key::<VERSION_METADATA_KEY_PREFIX>(version);
+
 		self.db
-			.insert(root_ndb_key(version), original_root_ndb_key)
+			.get(NonEmptyBz::from_owned_array(key))
 			.map_err(From::from)
 			.map_err(NodeDbError::Store)
 	}
+
+	/// Fetches the version tagged with checkpoint `tag`, if any.
+	pub fn fetch_checkpoint<T>(&self, tag: NonEmptyBz<T>) -> Result<Option<U63>>
+	where
+		T: AsRef<[u8]>,
+	{
+		self.db
+			.get(checkpoint_key(tag))
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.map(|version_bz| decode_version(version_bz.get()))
+			.transpose()
+			.map_err(From::from)
+	}
 }
 
 impl<DB> NodeDb<DB>
 where
-	DB: MutKVStore + KVStore,
+	DB: MutKVStore,
 {
-	pub fn save_non_overwririting_one_node(&self, node: &SavedNode) -> Result<Option<FetchedNode>> {
+	/// Writes all staged entries of `batch` in one go, atomically if the store supports it.
+	pub fn commit(&self, batch: WriteBatch) -> Result<()> {
+		self.db.write_batch(batch).map_err(From::from).map_err(NodeDbError::Store)
+	}
+
+	/// Overwrites `version` against checkpoint `tag`.
+	///
+	/// Returns true if `tag` already existed.
+	pub fn save_overwriting_checkpoint<T>(&self, tag: NonEmptyBz<T>, version: U63) -> Result<bool>
+	where
+		T: AsRef<[u8]>,
+	{
+		let version_bz = NonEmptyBz::from_owned_array(version.get().to_be_bytes());
+
+		self.db
+			.insert(checkpoint_key(tag), version_bz)
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)
+	}
+
+	/// Removes checkpoint `tag`.
+	///
+	/// Returns true if `tag` existed.
+	pub fn remove_checkpoint<T>(&self, tag: NonEmptyBz<T>) -> Result<bool>
+	where
+		T: AsRef<[u8]>,
+	{
+		self.db.remove(checkpoint_key(tag)).map_err(From::from).map_err(NodeDbError::Store)
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: KVStore,
+{
+	/// Stages `node` into `batch` unless its [`NodeKey`] is already saved, in which case the
+	/// saved node is returned.
+	pub fn stage_non_overwriting_one_node(
+		&self,
+		batch: &mut WriteBatch,
+		node: &SavedNode,
+	) -> Result<Option<FetchedNode>> {
 		let nk = node.node_key();
 		if let existing @ Some(_) = self.fetch_one_node(&nk)? {
 			return Ok(existing);
 		}
 
-		// TODO: remove this assert when fully certain about key conflict behavior
-		assert!(
-			!self.save_overwriting_one_node(node)?,
-			"key conflict must not occur",
-		);
+		self.stage_one_node(batch, node)?;
 
 		Ok(None)
 	}
@@ -131,94 +404,557 @@ impl<DB> NodeDb<DB>
 where
 	DB: KVIterator,
 {
+	/// Copies every entry into `target` as is, writing [`COPY_BATCH_LEN`] entries at a time.
+	///
+	/// Commit markers are copied last, so that an interrupted copy never marks a version whose
+	/// nodes are missing as committed.
+	///
+	/// Returns the number of copied entries.
+	pub fn copy_into<T>(&self, target: &T) -> Result<usize>
+	where
+		T: MutKVStore,
+	{
+		let (start, end) = COMMIT_MARKER_KEY_BOUNDS;
+		let marker = |bz| NonEmptyBz::from_borrowed_array(bz).as_slice();
+
+		let entries = self
+			.db
+			.iter(..marker(&start))
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.chain(self.db.iter(marker(&end)..).map_err(From::from).map_err(NodeDbError::Store)?)
+			.chain(
+				self.db
+					.iter(marker(&start)..marker(&end))
+					.map_err(From::from)
+					.map_err(NodeDbError::Store)?,
+			);
+
+		let (mut batch, mut copied) = (WriteBatch::new(), 0);
+
+		for kv in entries {
+			let (key, value) = kv.map_err(From::from).map_err(NodeDbError::Store)?;
+			batch.insert(key, value);
+
+			if batch.len() == COPY_BATCH_LEN {
+				copied += batch.len();
+				target
+					.write_batch(mem::take(&mut batch))
+					.map_err(From::from)
+					.map_err(NodeDbError::Store)?;
+			}
+		}
+
+		if !batch.is_empty() {
+			copied += batch.len();
+			target.write_batch(batch).map_err(From::from).map_err(NodeDbError::Store)?;
+		}
+
+		Ok(copied)
+	}
+
+	/// Fetches all checkpoint tags along with their versions, ordered by tag.
+	pub fn fetch_checkpoints(&self) -> Result<Vec<(NonEmptyBz<Bytes>, U63)>> {
+		let (start, end) = ([CHECKPOINT_KEY_PREFIX], [CHECKPOINT_KEY_PREFIX + 1]);
+
+		self.db
+			.iter(
+				NonEmptyBz::from_borrowed_array(&start).as_slice()
+					..NonEmptyBz::from_borrowed_array(&end).as_slice(),
+			)
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.map(|kv| {
+				let (key, version_bz) = kv.map_err(From::from).map_err(NodeDbError::Store)?;
+
+				// tags are non-empty, so checkpoint keys always exceed the prefix byte
+				let tag = NonEmptyBz::new(key.into_inner().slice(1..))
+					.ok_or(DeserializationError::ZeroLengthKey)?;
+
+				Ok((tag, decode_version(version_bz.get())?))
+			})
+			.collect()
+	}
+
+	/// Fetches the versions from `start` to `end` inclusive carrying a commit marker, along with
+	/// the root hashes held by their markers, in ascending order.
+	pub fn fetch_committed_root_hashes(
+		&self,
+		start: U63,
+		end: U63,
+	) -> Result<Vec<(U63, NodeHash)>> {
+		if start > end {
+			return Ok(vec![]);
+		}
+
+		let start = encoding::make_version_key::<COMMIT_MARKER_KEY_PREFIX>(start);
+		let end = encoding::make_version_key::<COMMIT_MARKER_KEY_PREFIX>(end);
+
+		self.db
+			.iter(
+				NonEmptyBz::from_borrowed_array(&start).as_slice()
+					..=NonEmptyBz::from_borrowed_array(&end).as_slice(),
+			)
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.map(|kv| {
+				let (key, root_hash) = kv.map_err(From::from).map_err(NodeDbError::Store)?;
+
+				let root_hash = root_hash
+					.get()
+					.as_ref()
+					.try_into()
+					.map_err(|_| DeserializationError::PrefixLengthMismatch)?;
+
+				Ok((decode_version(&key.get()[1..])?, root_hash))
+			})
+			.collect()
+	}
+
+	/// Fetches the latest version carrying a commit marker.
+	pub fn fetch_latest_committed_version(&self) -> Result<Option<U63>> {
+		let (start, end) = COMMIT_MARKER_KEY_BOUNDS;
+
+		let latest_marker = self
+			.db
+			.iter(
+				NonEmptyBz::from_borrowed_array(&start).as_slice()
+					..NonEmptyBz::from_borrowed_array(&end).as_slice(),
+			)
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.next_back();
+
+		decode_commit_marker(latest_marker)
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: KVStore + KVIterator,
+{
+	/// Fetches the root entry of the latest committed version.
+	///
+	/// A version only counts once its commit marker is written, so that a half-saved version
+	/// is never mistaken for the latest one.
 	pub fn fetch_latest_root_node(&self) -> Result<Option<(NodeKey, FetchedNode)>> {
-		let (root_ndb_key_bz, root_ndb_value_bz) = {
-			let Some((ndb_key_bz_max_version_max_nonce, _)) = self
+		let Some(version) = self.fetch_latest_committed_version()? else {
+			return self.fetch_latest_unmarked_root_node();
+		};
+
+		require_committed_root(self.fetch_root_node(version)?)
+	}
+
+	/// Fetches the root entry of the latest version having one, for stores written before
+	/// commit markers were introduced.
+	///
+	/// The root of a version is written after the rest of its nodes, so a version lacking it
+	/// was never fully saved.
+	fn fetch_latest_unmarked_root_node(&self) -> Result<Option<(NodeKey, FetchedNode)>> {
+		let mut search = UnmarkedRootSearch::new();
+
+		loop {
+			let latest_node = self
 				.db
-				.iter(NonEmptyBz::from_owned_array([NODE_DB_KEY_PREFIX]).as_ref_slice()..)
+				.iter(search.range())
 				.map_err(From::from)
 				.map_err(NodeDbError::Store)?
-				.next_back()
-				.transpose()
-				.map_err(From::from)
-				.map_err(NodeDbError::Store)?
-			else {
+				.next_back();
+
+			let Some(version) = search.skip_version_of(latest_node)? else {
 				return Ok(None);
 			};
 
-			// TODO: ascertain if block expression can return early when retrieved nonce is 1
+			if let root @ Some(_) = self.fetch_root_node(version)? {
+				return Ok(root);
+			}
+		}
+	}
+}
 
-			let prefix = ndb_key_bz_max_version_max_nonce
-				.get()
-				.get(..9)
-				.map(|bz| {
-					let mut prefix = [0; 9];
-					prefix.copy_from_slice(bz);
+impl<DB> NodeDb<DB>
+where
+	DB: MutKVStore + KVIterator,
+{
+	/// Removes the nodes, metadata and commit markers of every version after `version`, i.e.
+	/// the leftovers of interrupted saves.
+	///
+	/// Returns the number of removed entries.
+	pub fn remove_versions_after(&self, version: U63) -> Result<usize> {
+		let mut batch = WriteBatch::new();
 
-					NonEmptyBz::from_owned_array(prefix)
-				})
-				.ok_or(DeserializationError::InvalidInteger)?;
+		for (start, end) in version_ranges_after(version) {
+			// unwrap is safe because keys are non-empty
+			let (start, end) = (NonEmptyBz::new(start).unwrap(), [end]);
 
-			self.db
-				.iter(prefix.as_ref_slice()..)
+			let kvs = self
+				.db
+				.iter(start.as_ref_slice()..NonEmptyBz::from_borrowed_array(&end).as_slice())
 				.map_err(From::from)
-				.map_err(NodeDbError::Store)?
-				.next()
-				.transpose()
+				.map_err(NodeDbError::Store)?;
+
+			stage_removals(&mut batch, kvs)?;
+		}
+
+		let removed = batch.len();
+
+		if removed > 0 {
+			self.commit(batch)?;
+			self.evict_cached_versions_after(version)?;
+		}
+
+		Ok(removed)
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: AsyncKVStore,
+{
+	/// Asynchronous counterpart of [`NodeDb::snapshot`].
+	pub async fn snapshot_async(&self) -> Result<NodeDb<DB::Snapshot>> {
+		let db = self.db.snapshot().await.map_err(From::from).map_err(NodeDbError::Store)?;
+
+		Ok(self.with_db(db))
+	}
+
+	/// Asynchronous counterpart of [`NodeDb::fetch_one_node`].
+	pub async fn fetch_one_node_async(&self, nk: &NodeKey) -> Result<Option<FetchedNode>> {
+		let ndb_key = encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(nk);
+
+		let Some(stored) = self
+			.db
+			.get(NonEmptyBz::from_owned_array(ndb_key))
+			.await
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.map(make_stored_node)
+			.transpose()?
+		else {
+			return Ok(None);
+		};
+
+		let value = match stored.value_key() {
+			Some(value_key) => self
+				.db
+				.get(NonEmptyBz::from_owned_array(value_key))
+				.await
 				.map_err(From::from)
-				.map_err(NodeDbError::Store)?
-				.ok_or("must contain max version min nonce ndb key".into())
-				.map_err(NodeDbError::Store)?
+				.map_err(NodeDbError::Store)?,
+			None => None,
+		};
+
+		stored.complete(value).map(Some)
+	}
+
+	/// Asynchronous counterpart of [`NodeDb::fetch_root_node`].
+	pub async fn fetch_root_node_async(
+		&self,
+		version: U63,
+	) -> Result<Option<(NodeKey, FetchedNode)>> {
+		let nk = NodeKey::new(version, Self::NEW_ROOT_NONCE);
+
+		self.fetch_one_node_async(&nk).await.map(|root| root.map(|root| (nk, root)))
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: AsyncMutKVStore,
+{
+	/// Asynchronous counterpart of [`NodeDb::commit`].
+	pub async fn commit_async(&self, batch: WriteBatch) -> Result<()> {
+		self.db.write_batch(batch).await.map_err(From::from).map_err(NodeDbError::Store)
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: AsyncKVStore + AsyncKVIterator,
+{
+	/// Asynchronous counterpart of [`NodeDb::fetch_latest_root_node`].
+	pub async fn fetch_latest_root_node_async(&self) -> Result<Option<(NodeKey, FetchedNode)>> {
+		let Some(version) = self.fetch_latest_committed_version_async().await? else {
+			return self.fetch_latest_unmarked_root_node_async().await;
 		};
 
-		let nk = {
-			let (_, mut version_nonce_bz) = root_ndb_key_bz.split_first();
+		require_committed_root(self.fetch_root_node_async(version).await?)
+	}
+
+	/// Asynchronous counterpart of [`NodeDb::fetch_latest_committed_version`].
+	pub async fn fetch_latest_committed_version_async(&self) -> Result<Option<U63>> {
+		let (start, end) = COMMIT_MARKER_KEY_BOUNDS;
+
+		let latest_marker = self
+			.db
+			.iter(
+				NonEmptyBz::from_borrowed_array(&start).as_slice()
+					..NonEmptyBz::from_borrowed_array(&end).as_slice(),
+			)
+			.await
+			.map_err(From::from)
+			.map_err(NodeDbError::Store)?
+			.next_back();
+
+		decode_commit_marker(latest_marker)
+	}
+
+	/// Asynchronous counterpart of [`NodeDb::fetch_latest_unmarked_root_node`].
+	async fn fetch_latest_unmarked_root_node_async(
+		&self,
+	) -> Result<Option<(NodeKey, FetchedNode)>> {
+		let mut search = UnmarkedRootSearch::new();
+
+		loop {
+			let latest_node = self
+				.db
+				.iter(search.range())
+				.await
+				.map_err(From::from)
+				.map_err(NodeDbError::Store)?
+				.next_back();
 
-			let version = version_nonce_bz
-				.try_get_u64()
-				.ok()
-				.and_then(U63::new)
-				.ok_or(DeserializationError::InvalidInteger)?;
+			let Some(version) = search.skip_version_of(latest_node)? else {
+				return Ok(None);
+			};
+
+			if let root @ Some(_) = self.fetch_root_node_async(version).await? {
+				return Ok(root);
+			}
+		}
+	}
+}
+
+impl<DB> NodeDb<DB>
+where
+	DB: AsyncMutKVStore + AsyncKVIterator,
+{
+	/// Asynchronous counterpart of [`NodeDb::remove_versions_after`].
+	pub async fn remove_versions_after_async(&self, version: U63) -> Result<usize> {
+		let mut batch = WriteBatch::new();
+
+		for (start, end) in version_ranges_after(version) {
+			// unwrap is safe because keys are non-empty
+			let (start, end) = (NonEmptyBz::new(start).unwrap(), [end]);
+
+			let kvs = self
+				.db
+				.iter(start.as_ref_slice()..NonEmptyBz::from_borrowed_array(&end).as_slice())
+				.await
+				.map_err(From::from)
+				.map_err(NodeDbError::Store)?;
+
+			stage_removals(&mut batch, kvs)?;
+		}
 
-			let nonce = version_nonce_bz
-				.try_get_u32()
-				.ok()
-				.and_then(U31::new)
-				.ok_or(DeserializationError::InvalidInteger)?;
+		let removed = batch.len();
 
-			NodeKey::new(version, nonce)
+		if removed > 0 {
+			self.commit_async(batch).await?;
+			self.evict_cached_versions_after(version)?;
+		}
+
+		Ok(removed)
+	}
+}
+
+impl StoredNode {
+	/// Returns the key of the value to fetch to complete the node, if separated.
+	fn value_key(&self) -> Option<[u8; 33]> {
+		match self {
+			Self::SeparatedLeaf(_, value_hash) => Some(value_key(value_hash)),
+			Self::Fetched(_) => None,
+		}
+	}
+
+	/// Completes the node with `value`, fetched against [`StoredNode::value_key`].
+	fn complete(self, value: Option<NonEmptyBz<Bytes>>) -> Result<FetchedNode> {
+		match self {
+			Self::SeparatedLeaf(leaf, value_hash) => join_separated_value(leaf, &value_hash, value),
+			Self::Fetched(fetched) => Ok(fetched),
+		}
+	}
+}
+
+impl UnmarkedRootSearch {
+	fn new() -> Self {
+		// other key prefixes may follow, hence the node key range must be upper bounded
+		let mut end = [0; NODE_DB_KEY_LEN];
+		end[0] = NODE_DB_KEY_PREFIX + 1;
+
+		Self { end }
+	}
+
+	/// Returns the range of the node keys left to search.
+	fn range(&self) -> core::ops::Range<NonEmptyBz<&[u8]>> {
+		const START: [u8; 1] = [NODE_DB_KEY_PREFIX];
+
+		NonEmptyBz::from_borrowed_array(&START).as_slice()
+			..NonEmptyBz::from_borrowed_array(&self.end).as_slice()
+	}
+
+	/// Returns the version of `latest_node`, the last entry of [`UnmarkedRootSearch::range`],
+	/// leaving it out of the range left to search, or [`None`] once there is no node left.
+	fn skip_version_of<V, E>(
+		&mut self,
+		latest_node: Option<Result<(NonEmptyBz<Bytes>, V), E>>,
+	) -> Result<Option<U63>>
+	where
+		E: core::error::Error + Send + Sync + 'static,
+	{
+		let Some((ndb_key_bz, _)) =
+			latest_node.transpose().map_err(From::from).map_err(NodeDbError::Store)?
+		else {
+			return Ok(None);
 		};
 
-		Ok(Some((nk, make_fetched_node(root_ndb_value_bz)?)))
+		let version = *decode_node_key(&ndb_key_bz.get()[1..])?.version();
+		self.end = encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(&NodeKey::new(version, U31::MIN));
+
+		Ok(Some(version))
 	}
 }
 
-fn make_fetched_node<BZ>(ndb_value_bz: NonEmptyBz<BZ>) -> Result<FetchedNode, DeserializationError>
+/// Decodes the version of `latest_marker`, the last entry of [`COMMIT_MARKER_KEY_BOUNDS`].
+fn decode_commit_marker<V, E>(
+	latest_marker: Option<Result<(NonEmptyBz<Bytes>, V), E>>,
+) -> Result<Option<U63>>
+where
+	E: core::error::Error + Send + Sync + 'static,
+{
+	latest_marker
+		.transpose()
+		.map_err(From::from)
+		.map_err(NodeDbError::Store)?
+		.map(|(key, _)| decode_version(&key.get()[1..]))
+		.transpose()
+		.map_err(From::from)
+}
+
+/// Fails unless `root`, fetched for a committed version, exists.
+fn require_committed_root(
+	root: Option<(NodeKey, FetchedNode)>,
+) -> Result<Option<(NodeKey, FetchedNode)>> {
+	root.ok_or(NodeDbError::Other(
+		"committed version must have a root".into(),
+	))
+	.map(Some)
+}
+
+/// Stages the removal of the keys of `kvs`.
+fn stage_removals<V, E>(
+	batch: &mut WriteBatch,
+	kvs: impl Iterator<Item = Result<(NonEmptyBz<Bytes>, V), E>>,
+) -> Result<()>
+where
+	E: core::error::Error + Send + Sync + 'static,
+{
+	for kv in kvs {
+		let (key, _) = kv.map_err(From::from).map_err(NodeDbError::Store)?;
+		batch.remove(key);
+	}
+
+	Ok(())
+}
+
+/// Returns the key ranges holding the nodes, metadata and commit markers of the versions after
+/// `version`, see [`version_ranges_from`].
+fn version_ranges_after(version: U63) -> Vec<(Vec<u8>, u8)> {
+	version
+		.get()
+		.checked_add(1)
+		.and_then(U63::new)
+		.map(|next_version| version_ranges_from(next_version).to_vec())
+		.unwrap_or_default()
+}
+
+/// Returns the key ranges holding the nodes, metadata and commit markers of `version` onwards,
+/// each as its first key along with the prefix byte bounding it.
+fn version_ranges_from(version: U63) -> [(Vec<u8>, u8); 3] {
+	[
+		(
+			encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(&NodeKey::new(version, U31::MIN)).to_vec(),
+			NODE_DB_KEY_PREFIX + 1,
+		),
+		(
+			encoding::make_version_key::<VERSION_METADATA_KEY_PREFIX>(version).to_vec(),
+			VERSION_METADATA_KEY_PREFIX + 1,
+		),
+		(
+			encoding::make_version_key::<COMMIT_MARKER_KEY_PREFIX>(version).to_vec(),
+			COMMIT_MARKER_KEY_PREFIX + 1,
+		),
+	]
+}
+
+fn make_stored_node<BZ>(ndb_value_bz: NonEmptyBz<BZ>) -> Result<StoredNode, DeserializationError>
 where
 	BZ: AsRef<[u8]>,
 {
 	match ndb_value_bz.split_first() {
-		(NodeDb::<()>::EMPTY_ROOT_MARKER, _) => Ok(FetchedNode::EmptyRoot),
-		(NODE_DB_KEY_PREFIX, mut version_nonce_bz) => {
-			// check if valid version
-			let version = version_nonce_bz
-				.try_get_u64()
-				.ok()
-				.and_then(U63::new)
-				.ok_or(DeserializationError::InvalidInteger)?;
-
-			// check if valid nonce
-			let nonce = version_nonce_bz
-				.try_get_u32()
-				.ok()
-				.and_then(U31::new)
-				.ok_or(DeserializationError::InvalidInteger)?;
-
-			Ok(FetchedNode::ReferenceRoot(NodeKey::new(version, nonce)))
+		(NodeDb::<()>::EMPTY_ROOT_MARKER, _) => Ok(StoredNode::Fetched(FetchedNode::EmptyRoot)),
+		(NODE_DB_KEY_PREFIX, version_nonce_bz) => decode_node_key(version_nonce_bz)
+			.map(FetchedNode::ReferenceRoot)
+			.map(StoredNode::Fetched),
+		(checksum::CHECKSUM_TAG, checksummed) => deserialize_node(checksum::verify(checksummed)?),
+		_ => deserialize_node(ndb_value_bz.get().as_ref()),
+	}
+}
+
+/// Deserializes the node serialized in `bz`, decompressing it first if compressed.
+fn deserialize_node(bz: &[u8]) -> Result<StoredNode, DeserializationError> {
+	match bz.split_first() {
+		Some((&tag, compressed)) if compression::is_compressed(tag) => {
+			let decompressed = compression::decompress(tag, compressed)?;
+			deserialize_uncompressed_node(&decompressed)
+		},
+		_ => deserialize_uncompressed_node(bz),
+	}
+}
+
+fn deserialize_uncompressed_node(bz: &[u8]) -> Result<StoredNode, DeserializationError> {
+	match bz.split_first() {
+		Some((&SEPARATED_VALUE_TAG, leaf_bz)) => match DeserializedNode::deserialize(leaf_bz)? {
+			DeserializedNode::Leaf(leaf) => {
+				let value_hash = separated_value_hash(&leaf)?;
+				Ok(StoredNode::SeparatedLeaf(leaf, value_hash))
+			},
+			DeserializedNode::Inner(..) => Err(DeserializationError::InvalidMode),
 		},
-		_ => DeserializedNode::deserialize(ndb_value_bz.get().as_ref())
-			.map(FetchedNode::Deserialized),
+		_ => DeserializedNode::deserialize(bz)
+			.map(FetchedNode::Deserialized)
+			.map(StoredNode::Fetched),
+	}
+}
+
+/// Returns the hash referencing the value of a separated `leaf`.
+fn separated_value_hash(leaf: &LeafNode<Drafted>) -> Result<NodeHash, DeserializationError> {
+	leaf.value().as_ref().try_into().map_err(|_| DeserializationError::PrefixLengthMismatch)
+}
+
+/// Puts `value`, fetched against `value_hash`, back into the separated `leaf`.
+fn join_separated_value(
+	leaf: LeafNode<Drafted>,
+	value_hash: &NodeHash,
+	value: Option<NonEmptyBz<Bytes>>,
+) -> Result<FetchedNode> {
+	let value = value.ok_or(NodeDbError::MissingValue)?.into_inner();
+
+	if Sha256::digest(&value).as_slice() != value_hash {
+		return Err(DeserializationError::ValueHashMismatch.into());
 	}
+
+	// copied as fetched values may pin store resources, such as LMDB read transactions, for as
+	// long as the node lives
+	Ok(FetchedNode::Deserialized(DeserializedNode::Leaf(
+		leaf.with_value(Bytes::copy_from_slice(&value)),
+	)))
+}
+
+fn value_key(value_hash: &NodeHash) -> [u8; 33] {
+	let mut key = [VALUE_KEY_PREFIX; 33];
+	key[1..].copy_from_slice(value_hash);
+	key
 }
 
 fn root_ndb_key(version: U63) -> NonEmptyBz<[u8; NODE_DB_KEY_LEN]> {
@@ -226,3 +962,41 @@ fn root_ndb_key(version: U63) -> NonEmptyBz<[u8; NODE_DB_KEY_LEN]> {
 	let ndb_key_array = encoding::make_ndb_key::<NODE_DB_KEY_PREFIX>(&nk);
 	NonEmptyBz::from_owned_array(ndb_key_array)
 }
+
+fn checkpoint_key<T>(tag: NonEmptyBz<T>) -> NonEmptyBz<Bytes>
+where
+	T: AsRef<[u8]>,
+{
+	let mut key = BytesMut::with_capacity(tag.len().get() + 1);
+	key.put_u8(CHECKPOINT_KEY_PREFIX);
+	key.put_slice(tag.get().as_ref());
+
+	// unwrap is safe because key contains at least the prefix byte
+	NonEmptyBz::new(key.freeze()).unwrap()
+}
+
+fn decode_node_key(mut version_nonce_bz: &[u8]) -> Result<NodeKey, DeserializationError> {
+	let version = version_nonce_bz
+		.try_get_u64()
+		.ok()
+		.and_then(U63::new)
+		.ok_or(DeserializationError::InvalidInteger)?;
+
+	let nonce = version_nonce_bz
+		.try_get_u32()
+		.ok()
+		.filter(|_| version_nonce_bz.is_empty())
+		.and_then(U31::new)
+		.ok_or(DeserializationError::InvalidInteger)?;
+
+	Ok(NodeKey::new(version, nonce))
+}
+
+fn decode_version(mut version_bz: &[u8]) -> Result<U63, DeserializationError> {
+	version_bz
+		.try_get_u64()
+		.ok()
+		.filter(|_| version_bz.is_empty())
+		.and_then(U63::new)
+		.ok_or(DeserializationError::InvalidInteger)
+}
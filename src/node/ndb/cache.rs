@@ -0,0 +1,152 @@
+use core::num::NonZeroUsize;
+
+use std::{
+	collections::{BTreeMap, HashMap},
+	sync::RwLock,
+};
+
+use oblux::U63;
+
+use crate::{
+	NodeKey,
+	node::{Node, SavedNode},
+};
+
+/// Counters and occupancy of a node cache, see [`MutableTree::set_node_cache`].
+///
+/// [`MutableTree::set_node_cache`]: crate::MutableTree::set_node_cache
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCacheStats {
+	/// Lookups served from the cache.
+	pub hits: u64,
+
+	/// Lookups that fell through to the store.
+	pub misses: u64,
+
+	/// Number of cached nodes.
+	pub nodes: usize,
+
+	/// Estimated size of the cached nodes in bytes.
+	pub bytes: usize,
+}
+
+type CacheKey = (u64, u32);
+
+/// Least recently used [`SavedNode`]s keyed by their [`NodeKey`], bounded by their estimated
+/// size in bytes.
+#[derive(Debug)]
+pub(crate) struct NodeCache {
+	max_bytes: NonZeroUsize,
+	entries: HashMap<CacheKey, Entry>,
+	recency: BTreeMap<u64, CacheKey>,
+	tick: u64,
+	stats: NodeCacheStats,
+}
+
+#[derive(Debug)]
+struct Entry {
+	node: SavedNode,
+	tick: u64,
+	bytes: usize,
+}
+
+impl NodeCache {
+	pub fn new(max_bytes: NonZeroUsize) -> Self {
+		Self {
+			max_bytes,
+			entries: HashMap::new(),
+			recency: BTreeMap::new(),
+			tick: 0,
+			stats: NodeCacheStats::default(),
+		}
+	}
+
+	pub fn stats(&self) -> NodeCacheStats {
+		self.stats
+	}
+
+	/// Returns the node cached against `nk`, marking it as the most recently used.
+	pub fn get(&mut self, nk: &NodeKey) -> Option<SavedNode> {
+		let Some(entry) = self.entries.get_mut(&cache_key(nk)) else {
+			self.stats.misses += 1;
+			return None;
+		};
+
+		self.stats.hits += 1;
+		self.tick += 1;
+
+		self.recency.remove(&entry.tick);
+		self.recency.insert(self.tick, cache_key(nk));
+		entry.tick = self.tick;
+
+		Some(entry.node.clone())
+	}
+
+	/// Caches `node`, evicting the least recently used nodes beyond the size limit.
+	///
+	/// Nodes exceeding the limit on their own are not cached.
+	pub fn insert(&mut self, node: &SavedNode) {
+		let bytes = node_bytes(node);
+
+		if bytes > self.max_bytes.get() {
+			return;
+		}
+
+		let key = cache_key(&node.node_key());
+		self.remove(&key);
+
+		while self.stats.bytes + bytes > self.max_bytes.get() {
+			// unwrap is safe because cached bytes are non-zero while entries remain
+			let (_, lru_key) = self.recency.pop_first().unwrap();
+			self.remove(&lru_key);
+		}
+
+		self.tick += 1;
+		self.recency.insert(self.tick, key);
+		self.entries.insert(key, Entry { node: node.clone(), tick: self.tick, bytes });
+
+		self.stats.nodes += 1;
+		self.stats.bytes += bytes;
+	}
+
+	/// Evicts the nodes of every version after `version`.
+	pub fn evict_versions_after(&mut self, version: U63) {
+		let evicted =
+			self.entries.keys().filter(|(v, _)| *v > version.get()).copied().collect::<Vec<_>>();
+
+		evicted.iter().for_each(|key| self.remove(key));
+	}
+
+	fn remove(&mut self, key: &CacheKey) {
+		if let Some(entry) = self.entries.remove(key) {
+			self.recency.remove(&entry.tick);
+
+			self.stats.nodes -= 1;
+			self.stats.bytes -= entry.bytes;
+		}
+	}
+}
+
+fn cache_key(nk: &NodeKey) -> CacheKey {
+	(nk.version().get(), nk.nonce().get())
+}
+
+/// Estimates the bytes `node` takes up in memory once cached and handed out.
+///
+/// Hashes and child node keys are held inline by the node, whereas keys and values are on the
+/// heap, as are the reference counts and lock of the [`ArlockNode`] each hit is turned into.
+///
+/// [`ArlockNode`]: crate::node::ArlockNode
+fn node_bytes(node: &SavedNode) -> usize {
+	// entries are indexed by key and by recency
+	let bookkeeping = size_of::<(CacheKey, Entry)>() + size_of::<(u64, CacheKey)>();
+
+	let arlock = 2 * size_of::<usize>() + size_of::<RwLock<Node>>();
+
+	let heap_len = match node {
+		SavedNode::Leaf(leaf) => node.key().len().get() + leaf.value().len(),
+		SavedNode::Inner(_) => node.key().len().get(),
+	};
+
+	bookkeeping + arlock + heap_len
+}
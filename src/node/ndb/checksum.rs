@@ -0,0 +1,33 @@
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::encoding::DeserializationError;
+
+// like compression tags, it exceeds the first byte of any serialized node
+pub(super) const CHECKSUM_TAG: u8 = 0xFC;
+
+const CHECKSUM_LEN: usize = size_of::<u32>();
+
+/// Appends the CRC-32 checksum of `bz` to it, prefixed with [`CHECKSUM_TAG`].
+pub(super) fn append(bz: Bytes) -> Bytes {
+	let mut checksummed = BytesMut::with_capacity(bz.len() + CHECKSUM_LEN + 1);
+
+	checksummed.put_u8(CHECKSUM_TAG);
+	checksummed.put_slice(&bz);
+	checksummed.put_u32(crc32fast::hash(&bz));
+
+	checksummed.freeze()
+}
+
+/// Verifies the checksum trailing `checksummed`, stripped of its tag, returning the bytes it
+/// covers.
+pub(super) fn verify(checksummed: &[u8]) -> Result<&[u8], DeserializationError> {
+	let (bz, checksum) = checksummed
+		.split_last_chunk::<CHECKSUM_LEN>()
+		.ok_or(DeserializationError::ChecksumMismatch)?;
+
+	if crc32fast::hash(bz) != u32::from_be_bytes(*checksum) {
+		return Err(DeserializationError::ChecksumMismatch);
+	}
+
+	Ok(bz)
+}
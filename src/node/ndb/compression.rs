@@ -0,0 +1,105 @@
+#[cfg(feature = "lz4")]
+use std::io;
+
+use bytes::{BufMut, Bytes, BytesMut};
+
+use crate::encoding::{DeserializationError, SerializationError};
+
+/// Compression applied to serialized nodes before they are saved.
+///
+/// Compressed nodes are tagged with their algorithm, so trees read them back regardless of the
+/// compression they are configured with. Node hashes are computed over uncompressed nodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+	/// [Zstandard](https://facebook.github.io/zstd/) at the given compression `level`.
+	#[cfg(feature = "zstd")]
+	Zstd { level: i32 },
+
+	/// [LZ4](https://lz4.org/) block compression.
+	#[cfg(feature = "lz4")]
+	Lz4,
+}
+
+// the serialized bytes of a node start with the zigzag varint of its height, which stays below
+// 92 for up to 2^63 nodes as AVL trees are balanced, hence their first byte is at most 0xB6
+const ZSTD_TAG: u8 = 0xFE;
+
+const LZ4_TAG: u8 = 0xFD;
+
+// the best ratio LZ4 achieves, which bounds the decompressed length of any compressed node
+const MAX_RATIO: usize = 255;
+
+/// Returns whether `tag`, the first byte of a node-db value, marks a compressed node.
+pub(super) fn is_compressed(tag: u8) -> bool {
+	matches!(tag, ZSTD_TAG | LZ4_TAG)
+}
+
+/// Compresses `serialized` with `compression`, prefixed with its tag.
+///
+/// Returns `serialized` as is unless compressing shrinks it, or shrinks it beyond [`MAX_RATIO`]
+/// so that it could not be decompressed back.
+#[cfg_attr(
+	not(any(feature = "zstd", feature = "lz4")),
+	allow(unreachable_code, unused_variables)
+)]
+pub(super) fn compress(
+	compression: Compression,
+	serialized: Bytes,
+) -> Result<Bytes, SerializationError> {
+	let (tag, compressed): (u8, Vec<u8>) = match compression {
+		#[cfg(feature = "zstd")]
+		Compression::Zstd { level } => (ZSTD_TAG, zstd::bulk::compress(&serialized, level)?),
+		#[cfg(feature = "lz4")]
+		Compression::Lz4 => (LZ4_TAG, lz4_flex::compress_prepend_size(&serialized)),
+	};
+
+	// the tag takes up one more byte
+	if compressed.len() + 1 >= serialized.len()
+		|| serialized.len() > compressed.len().saturating_mul(MAX_RATIO)
+	{
+		return Ok(serialized);
+	}
+
+	let mut tagged = BytesMut::with_capacity(compressed.len() + 1);
+	tagged.put_u8(tag);
+	tagged.put_slice(&compressed);
+
+	Ok(tagged.freeze())
+}
+
+/// Decompresses `compressed` according to `tag`, see [`is_compressed`].
+///
+/// Nodes decompress to at most [`MAX_RATIO`] times their compressed length, so that corrupt ones
+/// do not allocate without bound.
+#[cfg_attr(not(any(feature = "zstd", feature = "lz4")), allow(unused_variables))]
+pub(super) fn decompress(tag: u8, compressed: &[u8]) -> Result<Vec<u8>, DeserializationError> {
+	match tag {
+		#[cfg(feature = "zstd")]
+		ZSTD_TAG => zstd::bulk::decompress(compressed, compressed.len().saturating_mul(MAX_RATIO))
+			.map_err(From::from),
+		#[cfg(feature = "lz4")]
+		LZ4_TAG => decompress_lz4(compressed),
+		_ => Err(DeserializationError::UnsupportedCompression(tag)),
+	}
+}
+
+/// Decompresses the LZ4 block in `compressed`, prefixed with its decompressed length.
+///
+/// The length prefix is checked against [`MAX_RATIO`], so that a corrupt one does not allocate up
+/// to 4 GiB.
+#[cfg(feature = "lz4")]
+fn decompress_lz4(compressed: &[u8]) -> Result<Vec<u8>, DeserializationError> {
+	let invalid_data = |err: String| io::Error::new(io::ErrorKind::InvalidData, err).into();
+
+	let (len, block) = lz4_flex::block::uncompressed_size(compressed)
+		.map_err(|err| invalid_data(err.to_string()))?;
+
+	if len > block.len().saturating_mul(MAX_RATIO) {
+		return Err(invalid_data(
+			"decompressed length exceeds the LZ4 ratio".into(),
+		));
+	}
+
+	lz4_flex::decompress(block, len).map_err(|err| invalid_data(err.to_string()))
+}
@@ -1,4 +1,4 @@
-use std::borrow::Cow;
+use std::{borrow::Cow, sync::PoisonError};
 
 use crate::encoding::{DeserializationError, SerializationError};
 
@@ -15,9 +15,21 @@ pub enum NodeDbError {
 	#[error("serialization error: {0}")]
 	Serialization(#[from] SerializationError),
 
+	#[error("missing value error: separated value must be stored along with its leaf")]
+	MissingValue,
+
+	#[error("poisoned lock error: lock must not be poisoned")]
+	PoisonedLock,
+
 	#[error("save unsuppported error: node kind cannot be saved")]
 	SaveUnsupported,
 
 	#[error("other error: {0}")]
 	Other(Cow<'static, str>),
 }
+
+impl<T> From<PoisonError<T>> for NodeDbError {
+	fn from(_err: PoisonError<T>) -> Self {
+		Self::PoisonedLock
+	}
+}
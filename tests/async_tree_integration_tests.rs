@@ -0,0 +1,248 @@
+#[path = "common/utils.rs"]
+mod utils;
+
+use core::ops::RangeBounds;
+
+use bytes::Bytes;
+use iavl::{
+	Get, MutableTree,
+	kvstore::{
+		AsyncKVIterator, AsyncKVStore, AsyncMutKVStore, KVIterator, KVStore, MutKVStore,
+		WriteBatch,
+		memory::{MemStore, MemStoreError},
+	},
+};
+use nebz::NonEmptyBz;
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// A [`MemStore`] yielding to the executor ahead of every access, as a store doing I/O would.
+#[derive(Clone, Default)]
+struct YieldingStore(MemStore);
+
+impl AsyncKVStore for YieldingStore {
+	type Error = MemStoreError;
+
+	type Snapshot = YieldingStore;
+
+	async fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		tokio::task::yield_now().await;
+		self.0.snapshot().map(Self)
+	}
+
+	async fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]> + Send,
+	{
+		tokio::task::yield_now().await;
+		KVStore::get(&self.0, key)
+	}
+}
+
+impl AsyncMutKVStore for YieldingStore {
+	type Error = MemStoreError;
+
+	async fn insert<K, V>(
+		&self,
+		key: NonEmptyBz<K>,
+		value: NonEmptyBz<V>,
+	) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]> + Send,
+		V: AsRef<[u8]> + Send,
+	{
+		tokio::task::yield_now().await;
+		MutKVStore::insert(&self.0, key, value)
+	}
+
+	async fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]> + Send,
+	{
+		tokio::task::yield_now().await;
+		MutKVStore::remove(&self.0, key)
+	}
+
+	async fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		tokio::task::yield_now().await;
+		MutKVStore::write_batch(&self.0, batch)
+	}
+}
+
+impl AsyncKVIterator for YieldingStore {
+	type Error = MemStoreError;
+
+	type FetchError = MemStoreError;
+
+	async fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		> + Send,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>> + Send,
+	{
+		tokio::task::yield_now().await;
+		let kvs = KVIterator::iter(&self.0, range)?.collect::<Vec<_>>();
+
+		Ok(kvs.into_iter())
+	}
+}
+
+#[tokio::test]
+async fn async_operations_match_sync_ones() {
+	// Arrange
+	let mut rng = StdRng::seed_from_u64(42);
+	let mut tree = MutableTree::new(MemStore::new());
+
+	let store = YieldingStore::default();
+	let mut async_tree = MutableTree::new(store.clone());
+
+	// Act & Assert
+	for round in 0..24 {
+		for _ in 0..64 {
+			let key = utils::make_nebz_bytes([rng.random_range(0..48u8), rng.random_range(0..4u8)]);
+
+			if rng.random_bool(0.3) {
+				let removed = async_tree.remove_async(key.clone()).await.unwrap();
+				assert_eq!(removed, tree.remove(key).unwrap());
+			} else {
+				let value = Bytes::from(vec![round; 2]);
+				let updated = async_tree.insert_async(key.clone(), value.clone()).await.unwrap();
+				assert_eq!(updated, tree.insert(key, value).unwrap());
+			}
+		}
+
+		let version = async_tree.save_async().await.unwrap();
+		assert_eq!(version, tree.save().unwrap());
+		assert_eq!(async_tree.saved_hash(), tree.saved_hash());
+
+		// nodes below the root are fetched anew by the next round
+		async_tree = MutableTree::load_latest_version_async(store.clone()).await.unwrap();
+		assert_eq!(async_tree.saved_hash(), tree.saved_hash());
+		assert_eq!(async_tree.size(), tree.size());
+
+		for i in 0..48u8 {
+			let key = utils::make_nebz_bytes([i, 0]);
+			assert_eq!(
+				async_tree.get_async(key.clone()).await.unwrap(),
+				tree.get(key).unwrap()
+			);
+		}
+	}
+}
+
+#[tokio::test]
+async fn load_latest_version_async_discards_half_saved_version() {
+	// Arrange
+	let store = YieldingStore::default();
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..16u8 {
+		tree.insert_async(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 2])).await.unwrap();
+	}
+
+	tree.save_async().await.unwrap();
+	let saved_hash = tree.saved_hash();
+
+	tree.insert_async(utils::make_nebz_bytes([16]), Bytes::from_static(b"half")).await.unwrap();
+	tree.save_async().await.unwrap();
+
+	let marker_key = utils::make_nebz_bytes([[b'c'].as_slice(), &2u64.to_be_bytes()].concat());
+	assert!(store.0.remove(marker_key).unwrap());
+
+	// Act
+	let loaded = MutableTree::load_latest_version_async(store.clone()).await.unwrap();
+
+	// Assert
+	assert_eq!(loaded.version().get(), 1);
+	assert_eq!(loaded.saved_hash(), saved_hash);
+
+	let root_key = [[b's'].as_slice(), &2u64.to_be_bytes(), &1u32.to_be_bytes()].concat();
+	assert!(!store.0.has(utils::make_nebz_bytes(root_key)).unwrap());
+
+	let (_, value) =
+		loaded.last_saved().unwrap().get_async(utils::make_nebz_bytes([7])).await.unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![7; 2])));
+}
+
+#[tokio::test]
+async fn async_operations_run_on_spawned_tasks() {
+	// Arrange
+	let store = YieldingStore::default();
+
+	// Act
+	let tree = tokio::spawn(async move {
+		let mut tree = MutableTree::new(store);
+
+		for i in 0..8u8 {
+			tree.insert_async(utils::make_nebz_bytes([i]), Bytes::from(vec![i])).await.unwrap();
+		}
+
+		tree.save_async().await.unwrap();
+		tree.remove_async(utils::make_nebz_bytes([3])).await.unwrap();
+
+		tree
+	})
+	.await
+	.unwrap();
+
+	// Assert
+	let (_, value) = tree.get_async(utils::make_nebz_bytes([3])).await.unwrap();
+	assert_eq!(value, None);
+
+	let (_, value) = tree.get_async(utils::make_nebz_bytes([4])).await.unwrap();
+	assert_eq!(value, Some(Bytes::from_static(&[4])));
+}
+
+#[tokio::test]
+async fn save_async_rejects_nodes_saved_by_another_tree() {
+	// Arrange
+	let store = YieldingStore::default();
+	let mut tree = MutableTree::new(store.clone());
+	let mut other_tree = MutableTree::new(store.clone());
+
+	tree.insert_async(utils::make_nebz_bytes([0]), Bytes::from_static(b"first")).await.unwrap();
+	tree.save_async().await.unwrap();
+
+	other_tree
+		.insert_async(utils::make_nebz_bytes([1]), Bytes::from_static(b"other"))
+		.await
+		.unwrap();
+
+	// Act
+	let err = other_tree.save_async().await.unwrap_err();
+
+	// Assert
+	assert!(
+		err.to_string().contains("conflicting node error: version 1"),
+		"{err}"
+	);
+
+	let loaded = MutableTree::load_latest_version_async(store).await.unwrap();
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+}
+
+#[tokio::test]
+async fn save_with_metadata_async_stores_metadata_along_with_version() {
+	// Arrange
+	let store = YieldingStore::default();
+	let mut tree = MutableTree::new(store.clone());
+
+	tree.insert_async(utils::make_nebz_bytes([0]), Bytes::from_static(b"value")).await.unwrap();
+
+	// Act
+	let version = tree.save_with_metadata_async(utils::make_nebz_bytes(b"block 1")).await.unwrap();
+
+	// Assert
+	let loaded = MutableTree::load_latest_version(store.0).unwrap();
+
+	assert_eq!(loaded.version(), version);
+	assert_eq!(
+		loaded.version_metadata(version).unwrap(),
+		Some(utils::make_nebz_bytes(b"block 1"))
+	);
+}
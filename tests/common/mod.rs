@@ -7,6 +7,7 @@ use redb::{Database, backends::InMemoryBackend};
 
 pub struct TestContext {
 	pub tree: MutableTree<RedbStore>,
+	pub store: RedbStore,
 }
 
 impl TestContext {
@@ -15,8 +16,7 @@ impl TestContext {
 			.create_with_backend(InMemoryBackend::new())
 			.map(Arc::new)
 			.map(|db| RedbStore::new(db, "test").unwrap())
-			.map(MutableTree::new)
-			.map(|tree| Self { tree })
+			.map(|store| Self { tree: MutableTree::new(store.clone()), store })
 			.expect("database error")
 	}
 }
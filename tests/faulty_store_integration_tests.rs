@@ -0,0 +1,171 @@
+#[path = "common/utils.rs"]
+mod utils;
+
+use bytes::Bytes;
+use iavl::{
+	Get, MutableTree,
+	kvstore::{
+		KVIterator,
+		faulty::{Fault, FaultyStore, Operation},
+		memory::MemStore,
+	},
+};
+
+fn make_saved_store() -> FaultyStore<MemStore> {
+	let store = FaultyStore::new(MemStore::new());
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..16u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 4])).unwrap();
+	}
+
+	tree.save().unwrap();
+
+	store
+}
+
+fn count_entries(store: &MemStore) -> usize {
+	KVIterator::iter(store, ..).unwrap().count()
+}
+
+#[test]
+fn failed_get_surfaces_as_store_error() {
+	// Arrange
+	let store = make_saved_store();
+	let tree = MutableTree::load_latest_version(store.clone()).unwrap();
+
+	store.inject(Operation::Get, 0, Fault::Fail).unwrap();
+
+	// Act
+	let err = tree.get(utils::make_nebz_bytes([3])).unwrap_err();
+
+	// Assert
+	assert!(
+		err.to_string().contains("Get call failed as scheduled"),
+		"{err}"
+	);
+
+	let (_, value) = tree.get(utils::make_nebz_bytes([3])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![3; 4])));
+}
+
+#[test]
+fn failed_iter_fails_loading() {
+	// Arrange
+	let store = make_saved_store();
+	store.inject(Operation::Iter, 0, Fault::Fail).unwrap();
+
+	// Act
+	let Err(err) = MutableTree::load_latest_version(store.clone()) else {
+		panic!("loading must fail");
+	};
+
+	// Assert
+	assert!(
+		err.to_string().contains("Iter call failed as scheduled"),
+		"{err}"
+	);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.version().get(), 1);
+}
+
+#[test]
+fn corrupted_get_surfaces_as_deserialization_error() {
+	// Arrange
+	let store = make_saved_store();
+	let tree = MutableTree::load_latest_version(store.clone()).unwrap();
+
+	store.inject(Operation::Get, 0, Fault::Corrupt).unwrap();
+
+	// Act
+	let err = tree.get(utils::make_nebz_bytes([3])).unwrap_err();
+
+	// Assert
+	assert!(err.to_string().contains("deserialization error"), "{err}");
+}
+
+#[test]
+fn torn_save_is_discarded_on_loading() {
+	// Arrange
+	let store = make_saved_store();
+	let mut tree = MutableTree::load_latest_version(store.clone()).unwrap();
+	let saved_hash = tree.saved_hash();
+	let saved_entries = count_entries(store.inner());
+
+	tree.insert(utils::make_nebz_bytes([16]), Bytes::from_static(b"torn")).unwrap();
+	store.inject(Operation::WriteBatch, 0, Fault::TornWrite).unwrap();
+	let write_batch_calls = store.calls(Operation::WriteBatch).unwrap();
+
+	// Act
+	let err = tree.save().unwrap_err();
+
+	// Assert
+	assert!(
+		err.to_string().contains("WriteBatch call failed as scheduled"),
+		"{err}"
+	);
+	assert_eq!(
+		store.calls(Operation::WriteBatch).unwrap(),
+		write_batch_calls + 1
+	);
+
+	assert!(count_entries(store.inner()) > saved_entries);
+
+	let loaded = MutableTree::load_latest_version(store.clone()).unwrap();
+	assert_eq!(loaded.version().get(), 1);
+	assert_eq!(loaded.saved_hash(), saved_hash);
+	assert_eq!(count_entries(store.inner()), saved_entries);
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([16])).unwrap();
+	assert_eq!(value, None);
+}
+
+#[test]
+fn save_retried_after_failed_write_batch_persists_every_node() {
+	// Arrange
+	let store = FaultyStore::new(MemStore::new());
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..16u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 4])).unwrap();
+	}
+
+	store.inject(Operation::WriteBatch, 0, Fault::Fail).unwrap();
+	tree.save().unwrap_err();
+
+	// Act
+	let version = tree.save().unwrap();
+
+	// Assert
+	assert_eq!(version.get(), 1);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+
+	for i in 0..16u8 {
+		let (_, value) = loaded.get(utils::make_nebz_bytes([i])).unwrap();
+		assert_eq!(value, Some(Bytes::from(vec![i; 4])));
+	}
+}
+
+#[test]
+fn save_over_nodes_of_torn_save_fails_until_reloaded() {
+	// Arrange
+	let store = make_saved_store();
+	let mut tree = MutableTree::load_latest_version(store.clone()).unwrap();
+
+	tree.insert(utils::make_nebz_bytes([16]), Bytes::from_static(b"torn")).unwrap();
+	store.inject(Operation::WriteBatch, 0, Fault::TornWrite).unwrap();
+	tree.save().unwrap_err();
+
+	// Act
+	let err = tree.save().unwrap_err();
+
+	// Assert
+	assert!(err.to_string().contains("conflicting node error"), "{err}");
+
+	let mut loaded = MutableTree::load_latest_version(store).unwrap();
+	loaded.insert(utils::make_nebz_bytes([16]), Bytes::from_static(b"whole")).unwrap();
+	assert_eq!(loaded.save().unwrap().get(), 2);
+}
@@ -0,0 +1,876 @@
+mod common;
+
+use core::{
+	ops::Bound,
+	sync::atomic::{AtomicUsize, Ordering},
+};
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use iavl::{
+	Get, MutableTree,
+	kvstore::{
+		KVIterator, KVStore, MutKVStore, WriteBatch,
+		encrypted::{EncryptedStore, EncryptedStoreError},
+		lmdb::{LmdbOptions, LmdbStore},
+		memory::{MemStore, MemStoreError},
+		prefix::PrefixStore,
+		redb::{RedbStore, RedbStoreError},
+		sled::SledStore,
+		sqlite::SqliteStore,
+	},
+};
+use nebz::NonEmptyBz;
+use oblux::U63;
+use redb::{Database, Durability};
+use rstest::rstest;
+use tempfile::TempDir;
+
+use self::common::{TestContext, utils};
+
+const ENCRYPTION_KEY: [u8; 32] = [7; 32];
+
+fn sled_store() -> SledStore {
+	let db = sled::Config::new().temporary(true).open().unwrap();
+
+	SledStore::new(&db, "test").unwrap()
+}
+
+fn lmdb_store() -> (TempDir, LmdbStore) {
+	let dir = tempfile::tempdir().unwrap();
+	let options = LmdbOptions::builder().map_size(1 << 24).build();
+
+	// SAFETY: the environment is opened once, in a directory of its own
+	let store = unsafe { LmdbStore::open(dir.path(), "test", options) }.unwrap();
+
+	(dir, store)
+}
+
+fn sqlite_store() -> SqliteStore {
+	SqliteStore::new(rusqlite::Connection::open_in_memory().unwrap(), "test").unwrap()
+}
+
+fn populated<S>(store: S) -> S
+where
+	S: MutKVStore,
+{
+	for key in ["a", "b", "ba", "c", "d"] {
+		store
+			.insert(
+				utils::make_nebz_bytes(key),
+				utils::make_nebz_bytes(key.repeat(2)),
+			)
+			.unwrap();
+	}
+
+	store
+}
+
+#[allow(clippy::type_complexity)]
+fn collect_range<S>(
+	store: &S,
+	range: (Bound<NonEmptyBz<&[u8]>>, Bound<NonEmptyBz<&[u8]>>),
+) -> (
+	Vec<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>)>,
+	Vec<NonEmptyBz<Bytes>>,
+)
+where
+	S: KVIterator,
+{
+	let kvs = store.iter(range.clone()).unwrap().collect::<Result<Vec<_>, _>>().unwrap();
+	let rev_keys = store.iter(range).unwrap().rev().map(|kv| kv.unwrap().0).collect();
+
+	(kvs, rev_keys)
+}
+
+#[rstest]
+#[case::full((Bound::Unbounded, Bound::Unbounded), vec!["a", "b", "ba", "c", "d"])]
+#[case::half_open((Bound::Included("b"), Bound::Excluded("c")), vec!["b", "ba"])]
+#[case::inclusive((Bound::Excluded("a"), Bound::Included("c")), vec!["b", "ba", "c"])]
+#[case::lower_bounded((Bound::Included("bb"), Bound::Unbounded), vec!["c", "d"])]
+#[case::empty((Bound::Excluded("c"), Bound::Excluded("c")), vec![])]
+#[case::inverted((Bound::Included("d"), Bound::Included("a")), vec![])]
+fn iter_yields_range_in_order(
+	#[case] range: (Bound<&'static str>, Bound<&'static str>),
+	#[case] expected_keys: Vec<&str>,
+) {
+	// Arrange
+	let mem_store = populated(MemStore::new());
+	let redb_store = populated(TestContext::new().store);
+	let sled_store = populated(sled_store());
+	let (_dir, lmdb_store) = lmdb_store();
+	let lmdb_store = populated(lmdb_store);
+	let lmdb_snapshot = KVStore::snapshot(&lmdb_store).unwrap();
+	let sqlite_store = populated(sqlite_store());
+	let encrypted_store = populated(EncryptedStore::new(MemStore::new(), &ENCRYPTION_KEY));
+	let key_encrypted_stores = [0, 1].map(|clear_prefix_len| {
+		populated(
+			EncryptedStore::new(MemStore::new(), &ENCRYPTION_KEY)
+				.with_key_encryption(clear_prefix_len),
+		)
+	});
+
+	let (start, end) = range;
+	let to_nebz = |key: &'static str| NonEmptyBz::new(key.as_bytes()).unwrap();
+	let range = (start.map(to_nebz), end.map(to_nebz));
+
+	// Act
+	let (mem_kvs, mem_rev_keys) = collect_range(&mem_store, range.clone());
+	let (redb_kvs, redb_rev_keys) = collect_range(&redb_store, range.clone());
+	let (sled_kvs, sled_rev_keys) = collect_range(&sled_store, range.clone());
+	let (lmdb_kvs, lmdb_rev_keys) = collect_range(&lmdb_store, range.clone());
+	let (lmdb_snapshot_kvs, lmdb_snapshot_rev_keys) = collect_range(&lmdb_snapshot, range.clone());
+	let (sqlite_kvs, sqlite_rev_keys) = collect_range(&sqlite_store, range.clone());
+	let (encrypted_kvs, encrypted_rev_keys) = collect_range(&encrypted_store, range.clone());
+	let key_encrypted = key_encrypted_stores.map(|store| collect_range(&store, range.clone()));
+
+	// Assert
+	let keys = mem_kvs.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>();
+	let expected = expected_keys.into_iter().map(utils::make_nebz_bytes).collect::<Vec<_>>();
+
+	assert_eq!(keys, expected);
+	assert_eq!(mem_rev_keys, expected.into_iter().rev().collect::<Vec<_>>());
+	assert!(mem_kvs.iter().all(|(k, v)| v.get().as_ref() == [k.get().as_ref(); 2].concat()));
+
+	assert_eq!(mem_kvs, redb_kvs);
+	assert_eq!(mem_rev_keys, redb_rev_keys);
+	assert_eq!(mem_kvs, sled_kvs);
+	assert_eq!(mem_rev_keys, sled_rev_keys);
+	assert_eq!(mem_kvs, lmdb_kvs);
+	assert_eq!(mem_rev_keys, lmdb_rev_keys);
+	assert_eq!(mem_kvs, lmdb_snapshot_kvs);
+	assert_eq!(mem_rev_keys, lmdb_snapshot_rev_keys);
+	assert_eq!(mem_kvs, sqlite_kvs);
+	assert_eq!(mem_rev_keys, sqlite_rev_keys);
+	assert_eq!(mem_kvs, encrypted_kvs);
+	assert_eq!(mem_rev_keys, encrypted_rev_keys);
+
+	for (kvs, rev_keys) in key_encrypted {
+		assert_eq!(mem_kvs, kvs);
+		assert_eq!(mem_rev_keys, rev_keys);
+	}
+}
+
+/// Inserts more keys than a store reads at once, then checks that iterating from both ends in
+/// turn yields each of them once and in order.
+fn assert_iter_meets_from_both_ends<S>(store: &S)
+where
+	S: MutKVStore + KVIterator,
+{
+	let keys = (0..1000u16).map(u16::to_be_bytes).collect::<Vec<_>>();
+
+	for key in &keys {
+		store
+			.insert(
+				NonEmptyBz::from_borrowed_array(key),
+				NonEmptyBz::from_borrowed_array(key),
+			)
+			.unwrap();
+	}
+
+	assert_iter_meets_from_both_ends_over(store, &keys);
+}
+
+fn assert_iter_meets_from_both_ends_over<S>(store: &S, keys: &[[u8; 2]])
+where
+	S: KVIterator,
+{
+	let mut iter = store.iter(..).unwrap();
+	let (mut front, mut back) = (vec![], vec![]);
+
+	loop {
+		match iter.next() {
+			Some(kv) => front.push(kv.unwrap().0.get().to_vec()),
+			None => break,
+		}
+
+		match iter.next_back() {
+			Some(kv) => back.push(kv.unwrap().0.get().to_vec()),
+			None => break,
+		}
+	}
+
+	front.extend(back.into_iter().rev());
+
+	assert_eq!(front, keys.iter().map(Vec::from).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_meets_from_both_ends_across_chunks() {
+	// Arrange
+	let mem_store = MemStore::new();
+	let sqlite_store = sqlite_store();
+	let (_dir, lmdb_store) = lmdb_store();
+	let prefix_store = PrefixStore::new(MemStore::new(), "bank/");
+	let encrypted_store = EncryptedStore::new(MemStore::new(), &ENCRYPTION_KEY);
+	let key_encrypted_stores = [0, 1, 2].map(|clear_prefix_len| {
+		EncryptedStore::new(MemStore::new(), &ENCRYPTION_KEY).with_key_encryption(clear_prefix_len)
+	});
+
+	// Act & Assert
+	assert_iter_meets_from_both_ends(&mem_store);
+	assert_iter_meets_from_both_ends(&sqlite_store);
+	assert_iter_meets_from_both_ends(&lmdb_store);
+	assert_iter_meets_from_both_ends_over(
+		&KVStore::snapshot(&lmdb_store).unwrap(),
+		&(0..1000u16).map(u16::to_be_bytes).collect::<Vec<_>>(),
+	);
+	assert_iter_meets_from_both_ends(&prefix_store);
+	assert_iter_meets_from_both_ends(&encrypted_store);
+	key_encrypted_stores.iter().for_each(assert_iter_meets_from_both_ends);
+}
+
+#[rstest]
+#[case::text(b"bank/")]
+#[case::max_byte(b"\xff")]
+#[case::max_suffix(b"a\xff\xff")]
+fn prefix_store_hides_neighbouring_keys(#[case] prefix: &'static [u8]) {
+	// Arrange
+	let mem_store = populated(MemStore::new());
+	let shared = MemStore::new();
+	let prefix_store = populated(PrefixStore::new(shared.clone(), prefix));
+
+	// keys sorting right around the prefixed ones, but outside of them
+	let mut successor = prefix.to_vec();
+	*successor.last_mut().unwrap() = successor.last().unwrap().wrapping_add(1);
+
+	let neighbours = [
+		&prefix[..prefix.len() - 1],
+		prefix,
+		&successor,
+		&[0x00],
+		&[0xff; 4],
+	];
+
+	for key in neighbours.into_iter().filter(|key| !key.starts_with(prefix) || key == &prefix) {
+		if let Some(key) = NonEmptyBz::new(key) {
+			shared.insert(key.clone(), key).unwrap();
+		}
+	}
+
+	let ranges = [
+		(Bound::Unbounded, Bound::Unbounded),
+		(Bound::Excluded("a"), Bound::Unbounded),
+		(Bound::Unbounded, Bound::Included("c")),
+	];
+
+	for (start, end) in ranges {
+		let to_nebz = |key: &'static str| NonEmptyBz::new(key.as_bytes()).unwrap();
+		let range = (start.map(to_nebz), end.map(to_nebz));
+
+		// Act
+		let (kvs, rev_keys) = collect_range(&prefix_store, range.clone());
+
+		// Assert
+		let (expected_kvs, expected_rev_keys) = collect_range(&mem_store, range);
+
+		assert_eq!(kvs, expected_kvs);
+		assert_eq!(rev_keys, expected_rev_keys);
+	}
+
+	assert_eq!(
+		prefix_store.get(utils::make_nebz_bytes("ba")).unwrap(),
+		Some(utils::make_nebz_bytes("baba"))
+	);
+	assert_eq!(
+		shared.get(NonEmptyBz::new([prefix, b"ba"].concat()).unwrap()).unwrap(),
+		Some(utils::make_nebz_bytes("baba"))
+	);
+}
+
+#[test]
+fn mutable_trees_share_one_store_through_prefixes() {
+	// Arrange
+	let store = TestContext::new().store;
+	let mut bank = MutableTree::new(PrefixStore::new(store.clone(), "bank/"));
+	let mut staking = MutableTree::new(PrefixStore::new(store.clone(), "staking/"));
+	let mut redb_bank = TestContext::new().tree;
+
+	for i in 0..32u8 {
+		bank.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+		redb_bank.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+		staking.insert(utils::make_nebz_bytes([i, i]), Bytes::from(vec![i; 2])).unwrap();
+	}
+
+	bank.save().unwrap();
+	redb_bank.save().unwrap();
+	staking.save().unwrap();
+	staking.save().unwrap();
+
+	// Act
+	let loaded_bank =
+		MutableTree::load_latest_version(PrefixStore::new(store.clone(), "bank/")).unwrap();
+	let loaded_staking =
+		MutableTree::load_latest_version(PrefixStore::new(store, "staking/")).unwrap();
+
+	// Assert
+	assert_eq!(bank.saved_hash(), redb_bank.saved_hash());
+	assert_eq!(loaded_bank.saved_hash(), bank.saved_hash());
+	assert_eq!(loaded_bank.version(), bank.version());
+	assert_eq!(loaded_staking.saved_hash(), staking.saved_hash());
+	assert_eq!(loaded_staking.version(), staking.version());
+
+	let (_, value) = loaded_staking.get(utils::make_nebz_bytes([7, 7])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![7; 2])));
+
+	let (_, value) = loaded_bank.get(utils::make_nebz_bytes([7, 7])).unwrap();
+	assert_eq!(value, None);
+}
+
+#[test]
+fn mem_store_snapshot_is_isolated_from_writes() {
+	// Arrange
+	let store = populated(MemStore::new());
+	let shared = store.clone();
+
+	// Act
+	let snapshot = store.snapshot().unwrap();
+
+	assert!(store.insert(utils::make_nebz_bytes("a"), utils::make_nebz_bytes("new")).unwrap());
+	assert!(store.remove(utils::make_nebz_bytes("d")).unwrap());
+	assert!(!snapshot.insert(utils::make_nebz_bytes("e"), utils::make_nebz_bytes("e")).unwrap());
+
+	// Assert
+	let get = |store: &MemStore, key| store.get(utils::make_nebz_bytes(key)).unwrap();
+
+	assert_eq!(get(&shared, "a"), Some(utils::make_nebz_bytes("new")));
+	assert_eq!(get(&shared, "d"), None);
+	assert_eq!(get(&shared, "e"), None);
+
+	assert_eq!(get(&snapshot, "a"), Some(utils::make_nebz_bytes("aa")));
+	assert_eq!(get(&snapshot, "d"), Some(utils::make_nebz_bytes("dd")));
+	assert_eq!(get(&snapshot, "e"), Some(utils::make_nebz_bytes("e")));
+}
+
+#[test]
+fn redb_snapshot_is_isolated_from_writes() {
+	// Arrange
+	let store = populated(TestContext::new().store);
+
+	// Act
+	let snapshot = KVStore::snapshot(&store).unwrap();
+
+	store.insert(utils::make_nebz_bytes("a"), utils::make_nebz_bytes("new")).unwrap();
+	store.remove(utils::make_nebz_bytes("d")).unwrap();
+
+	// Assert
+	assert_eq!(
+		snapshot.get(utils::make_nebz_bytes("a")).unwrap(),
+		Some(utils::make_nebz_bytes("aa"))
+	);
+	assert!(snapshot.has(utils::make_nebz_bytes("d")).unwrap());
+	assert!(!store.has(utils::make_nebz_bytes("d")).unwrap());
+
+	let (kvs, _) = collect_range(&snapshot, (Bound::Unbounded, Bound::Unbounded));
+	let (expected, _) = collect_range(
+		&populated(MemStore::new()),
+		(Bound::Unbounded, Bound::Unbounded),
+	);
+
+	assert_eq!(kvs, expected);
+}
+
+#[test]
+fn mutable_tree_over_mem_store_reloads_saved_version() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+	let mut redb_tree = TestContext::new().tree;
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+		redb_tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+	redb_tree.save().unwrap();
+
+	// Act
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	// Assert
+	assert_eq!(tree.saved_hash(), redb_tree.saved_hash());
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+	assert_eq!(loaded.size(), tree.size());
+
+	for i in 0..32u8 {
+		let (_, value) = loaded.get(utils::make_nebz_bytes([i])).unwrap();
+		assert_eq!(value, Some(Bytes::from(vec![i; 3])));
+	}
+}
+
+#[test]
+fn mutable_tree_over_encrypted_store_hides_keys_and_values() {
+	// Arrange
+	let inner = MemStore::new();
+	let store = EncryptedStore::new(inner.clone(), &ENCRYPTION_KEY).with_key_encryption(9);
+	let mut tree = MutableTree::new(store);
+	let mut plain_tree = MutableTree::new(MemStore::new());
+
+	let key = |i: u8| utils::make_nebz_bytes(format!("account/{i}"));
+	let value = |i: u8| Bytes::from(format!("balance of {i}"));
+
+	for version in 0..3u8 {
+		for i in (version * 8)..(version * 8 + 12) {
+			tree.insert(key(i), value(i)).unwrap();
+			plain_tree.insert(key(i), value(i)).unwrap();
+		}
+
+		tree.save().unwrap();
+		plain_tree.save().unwrap();
+	}
+
+	// Act
+	let loaded = MutableTree::load_latest_version(
+		EncryptedStore::new(inner.clone(), &ENCRYPTION_KEY).with_key_encryption(9),
+	)
+	.unwrap();
+	let wrongly_keyed = EncryptedStore::new(inner.clone(), &[8; 32]).with_key_encryption(9);
+	let commit_marker = wrongly_keyed.get(utils::make_nebz_bytes(
+		[[b'c'].as_slice(), &3u64.to_be_bytes()].concat(),
+	));
+
+	// Assert
+	assert_eq!(tree.saved_hash(), plain_tree.saved_hash());
+	assert_eq!(loaded.saved_hash(), plain_tree.saved_hash());
+	assert_eq!(loaded.version().get(), 3);
+
+	for i in 0..28u8 {
+		let (_, loaded_value) = loaded.get(key(i)).unwrap();
+		assert_eq!(loaded_value, Some(value(i)));
+	}
+
+	let stored = inner.iter(..).unwrap().map(|kv| kv.unwrap()).collect::<Vec<_>>();
+	let leaks = |bz: &[u8]| bz.windows(b"account/".len()).any(|w| w == b"account/");
+	assert!(stored.iter().all(|(k, v)| !leaks(k.get()) && !leaks(v.get())));
+
+	assert!(matches!(
+		commit_marker,
+		Err(EncryptedStoreError::Decryption)
+	));
+}
+
+#[test]
+fn write_batch_applies_ops_in_order() {
+	// Arrange
+	let mem_store = populated(MemStore::new());
+	let redb_store = populated(TestContext::new().store);
+	let sled_store = populated(sled_store());
+	let (_dir, lmdb_store) = lmdb_store();
+	let lmdb_store = populated(lmdb_store);
+	let sqlite_store = populated(sqlite_store());
+
+	let mut batch = WriteBatch::new();
+	batch.insert(utils::make_nebz_bytes("e"), utils::make_nebz_bytes("ee"));
+	batch.remove(utils::make_nebz_bytes("e"));
+	batch.remove(utils::make_nebz_bytes("a"));
+	batch.insert(utils::make_nebz_bytes("b"), utils::make_nebz_bytes("new"));
+	batch.remove(utils::make_nebz_bytes("c"));
+	batch.insert(utils::make_nebz_bytes("c"), utils::make_nebz_bytes("cc"));
+
+	// Act
+	mem_store.write_batch(batch.clone()).unwrap();
+	redb_store.write_batch(batch.clone()).unwrap();
+	sled_store.write_batch(batch.clone()).unwrap();
+	lmdb_store.write_batch(batch.clone()).unwrap();
+	sqlite_store.write_batch(batch).unwrap();
+
+	// Assert
+	let (mem_kvs, _) = collect_range(&mem_store, (Bound::Unbounded, Bound::Unbounded));
+	let (redb_kvs, _) = collect_range(&redb_store, (Bound::Unbounded, Bound::Unbounded));
+	let (sled_kvs, _) = collect_range(&sled_store, (Bound::Unbounded, Bound::Unbounded));
+	let (lmdb_kvs, _) = collect_range(&lmdb_store, (Bound::Unbounded, Bound::Unbounded));
+	let (sqlite_kvs, _) = collect_range(&sqlite_store, (Bound::Unbounded, Bound::Unbounded));
+
+	let expected = [("b", "new"), ("ba", "baba"), ("c", "cc"), ("d", "dd")]
+		.map(|(k, v)| (utils::make_nebz_bytes(k), utils::make_nebz_bytes(v)));
+
+	assert_eq!(mem_kvs, expected);
+	assert_eq!(redb_kvs, expected);
+	assert_eq!(sled_kvs, expected);
+	assert_eq!(lmdb_kvs, expected);
+	assert_eq!(sqlite_kvs, expected);
+}
+
+#[derive(Clone, Default)]
+struct CountingStore {
+	inner: MemStore,
+	writes: Arc<AtomicUsize>,
+}
+
+impl KVStore for CountingStore {
+	type Error = MemStoreError;
+
+	type Snapshot = MemStore;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		self.inner.snapshot()
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.inner.get(key)
+	}
+}
+
+impl MutKVStore for CountingStore {
+	type Error = MemStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		self.writes.fetch_add(1, Ordering::Relaxed);
+		self.inner.insert(key, value)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.writes.fetch_add(1, Ordering::Relaxed);
+		self.inner.remove(key)
+	}
+
+	fn write_batch(&self, batch: WriteBatch) -> Result<(), Self::Error> {
+		self.writes.fetch_add(1, Ordering::Relaxed);
+		self.inner.write_batch(batch)
+	}
+}
+
+#[rstest]
+#[case::drafted_root(16)]
+#[case::saved_root(0)]
+#[case::empty_root(-1)]
+fn save_writes_version_in_one_batch(#[case] new_keys: i8) {
+	// Arrange
+	let store = CountingStore::default();
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+
+	if new_keys < 0 {
+		for i in 0..32u8 {
+			tree.remove(utils::make_nebz_bytes([i])).unwrap();
+		}
+	}
+
+	for i in 0..new_keys.max(0) as u8 {
+		tree.insert(utils::make_nebz_bytes([i, i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	let writes = store.writes.load(Ordering::Relaxed);
+
+	// Act
+	tree.save_with_metadata(utils::make_nebz_bytes("metadata")).unwrap();
+
+	// Assert
+	assert_eq!(store.writes.load(Ordering::Relaxed), writes + 1);
+}
+
+#[test]
+fn mutable_tree_over_sled_store_reloads_saved_version() {
+	// Arrange
+	let store = sled_store();
+	let mut tree = MutableTree::new(store.clone());
+	let mut redb_tree = TestContext::new().tree;
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+		redb_tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+	redb_tree.save().unwrap();
+
+	tree.remove(utils::make_nebz_bytes([7])).unwrap();
+	redb_tree.remove(utils::make_nebz_bytes([7])).unwrap();
+
+	tree.save().unwrap();
+	redb_tree.save().unwrap();
+
+	// Act
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	// Assert
+	assert_eq!(tree.saved_hash(), redb_tree.saved_hash());
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+	assert_eq!(loaded.version(), tree.version());
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([7])).unwrap();
+	assert_eq!(value, None);
+
+	let value = loaded.get_versioned(utils::make_nebz_bytes([7]), U63::ONE).unwrap().1;
+	assert_eq!(value, Some(Bytes::from(vec![7; 3])));
+}
+
+#[test]
+fn mutable_tree_over_sqlite_store_reloads_after_reopen() {
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("iavl.sqlite");
+
+	let mut tree = MutableTree::new(SqliteStore::open(&path, "iavl").unwrap());
+	let mut redb_tree = TestContext::new().tree;
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+		redb_tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+	redb_tree.save().unwrap();
+
+	let (hash, version) = (tree.saved_hash(), tree.version());
+	drop(tree);
+
+	// Act
+	let loaded =
+		MutableTree::load_latest_version(SqliteStore::open(&path, "iavl").unwrap()).unwrap();
+
+	// Assert
+	assert_eq!(hash, redb_tree.saved_hash());
+	assert_eq!(loaded.saved_hash(), hash);
+	assert_eq!(loaded.version(), version);
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([7])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![7; 3])));
+}
+
+#[test]
+fn lmdb_snapshot_values_outlive_later_writes() {
+	// Arrange
+	let (_dir, store) = lmdb_store();
+	let store = populated(store);
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+
+	// Act
+	let snapshot = KVStore::snapshot(&store).unwrap();
+	let value = snapshot.get(utils::make_nebz_bytes("a")).unwrap().unwrap();
+	drop(snapshot);
+
+	store.insert(utils::make_nebz_bytes("a"), utils::make_nebz_bytes("new")).unwrap();
+	tree.remove(utils::make_nebz_bytes([3])).unwrap();
+	tree.save().unwrap();
+
+	// Assert
+	assert_eq!(value, utils::make_nebz_bytes("aa"));
+	assert_eq!(
+		store.get(utils::make_nebz_bytes("a")).unwrap(),
+		Some(utils::make_nebz_bytes("new"))
+	);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+
+	let value = loaded.get_versioned(utils::make_nebz_bytes([3]), U63::ONE).unwrap().1;
+	assert_eq!(value, Some(Bytes::from(vec![3; 3])));
+}
+
+#[test]
+fn redb_store_compacts_once_handed_back_by_tree() {
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+	let db = Database::create(dir.path().join("store.redb")).map(Arc::new).unwrap();
+	let store = RedbStore::new(db, "test").unwrap();
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..64u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 256])).unwrap();
+	}
+
+	tree.save().unwrap();
+
+	for i in 0..64u8 {
+		tree.remove(utils::make_nebz_bytes([i])).unwrap();
+	}
+
+	tree.save().unwrap();
+	let saved_hash = tree.saved_hash();
+
+	let mut shared = store;
+
+	// Act
+	let shared_compacted = shared.compact();
+	drop(shared);
+
+	let mut store = tree.into_db();
+	let compacted = store.compact();
+
+	// Assert
+	assert!(matches!(
+		shared_compacted,
+		Err(RedbStoreError::SharedDatabase)
+	));
+	assert!(compacted.is_ok());
+
+	let reopened = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(reopened.saved_hash(), saved_hash);
+}
+
+#[cfg(feature = "rocksdb")]
+#[test]
+fn rocksdb_store_keeps_column_families_apart() {
+	use iavl::kvstore::rocksdb::RocksDbStore;
+	use rocksdb::{DB, Options};
+
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+
+	let mut options = Options::default();
+	options.create_if_missing(true);
+	options.create_missing_column_families(true);
+
+	let db = Arc::new(DB::open_cf(&options, dir.path(), ["iavl"]).unwrap());
+
+	let default_store = populated(RocksDbStore::new(db.clone()));
+	let store = RocksDbStore::with_column_family(db.clone(), "iavl").unwrap();
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..32u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+
+	// Act
+	let snapshot = KVStore::snapshot(&default_store).unwrap();
+	default_store.remove(utils::make_nebz_bytes("a")).unwrap();
+
+	// Assert
+	assert!(RocksDbStore::with_column_family(db, "missing").is_err());
+
+	let (kvs, rev_keys) = collect_range(&snapshot, (Bound::Unbounded, Bound::Unbounded));
+	let (expected, expected_rev_keys) = collect_range(
+		&populated(MemStore::new()),
+		(Bound::Unbounded, Bound::Unbounded),
+	);
+
+	assert_eq!(kvs, expected);
+	assert_eq!(rev_keys, expected_rev_keys);
+	assert!(!default_store.has(utils::make_nebz_bytes("a")).unwrap());
+	assert!(!default_store.has(utils::make_nebz_bytes("s")).unwrap());
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+}
+
+#[rstest]
+#[case::eventual(Durability::Eventual, false)]
+#[case::immediate(Durability::Immediate, false)]
+#[case::two_phase_commit(Durability::Immediate, true)]
+fn redb_store_with_durability_options_reopens_saved_tree(
+	#[case] durability: Durability,
+	#[case] two_phase_commit: bool,
+) {
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+	let path = dir.path().join("tree.redb");
+
+	let open = |path| {
+		let db = Database::create(path).map(Arc::new).unwrap();
+
+		RedbStore::builder()
+			.db(db)
+			.table_name("test")
+			.durability(durability)
+			.two_phase_commit(two_phase_commit)
+			.build()
+			.unwrap()
+	};
+
+	let mut tree = MutableTree::new(open(&path));
+
+	for i in 0..16u8 {
+		tree.insert(utils::make_nebz_bytes([i]), Bytes::from(vec![i; 3])).unwrap();
+	}
+
+	tree.save().unwrap();
+	let saved_hash = tree.saved_hash();
+
+	// Act
+	drop(tree);
+	let reopened = MutableTree::load_latest_version(open(&path)).unwrap();
+
+	// Assert
+	assert_eq!(reopened.saved_hash(), saved_hash);
+
+	let (_, value) = reopened.get(utils::make_nebz_bytes([5])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![5; 3])));
+}
+
+#[test]
+fn redb_store_compacts_once_unshared() {
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+	let db = Database::create(dir.path().join("store.redb")).map(Arc::new).unwrap();
+	let mut store = RedbStore::new(db, "test").unwrap();
+
+	let mut batch = WriteBatch::new();
+	(0..1024u16).for_each(|i| {
+		batch.insert(
+			utils::make_nebz_bytes(i.to_be_bytes()),
+			utils::make_nebz_bytes([7; 256]),
+		)
+	});
+	store.write_batch(batch).unwrap();
+
+	let mut batch = WriteBatch::new();
+	(0..1024u16).for_each(|i| batch.remove(utils::make_nebz_bytes(i.to_be_bytes())));
+	store.write_batch(batch).unwrap();
+
+	// Act
+	let clone = store.clone();
+	let shared = store.compact();
+	drop(clone);
+
+	let compacted = store.compact();
+
+	// Assert
+	assert!(matches!(shared, Err(RedbStoreError::SharedDatabase)));
+	assert!(compacted.unwrap());
+
+	store.insert(utils::make_nebz_bytes("a"), utils::make_nebz_bytes("b")).unwrap();
+	assert_eq!(
+		store.get(utils::make_nebz_bytes("a")).unwrap(),
+		Some(utils::make_nebz_bytes("b"))
+	);
+}
+
+#[cfg(feature = "rocksdb")]
+#[test]
+fn rocksdb_iter_meets_from_both_ends_across_chunks() {
+	use iavl::kvstore::rocksdb::RocksDbStore;
+	use rocksdb::{DB, Options};
+
+	// Arrange
+	let dir = tempfile::tempdir().unwrap();
+
+	let mut options = Options::default();
+	options.create_if_missing(true);
+	options.create_missing_column_families(true);
+
+	let db = Arc::new(DB::open_cf(&options, dir.path(), ["iavl"]).unwrap());
+	let store = RocksDbStore::with_column_family(db, "iavl").unwrap();
+
+	// Act & Assert
+	assert_iter_meets_from_both_ends(&store);
+	assert_iter_meets_from_both_ends_over(
+		&KVStore::snapshot(&store).unwrap(),
+		&(0..1000u16).map(u16::to_be_bytes).collect::<Vec<_>>(),
+	);
+}
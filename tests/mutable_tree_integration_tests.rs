@@ -1,10 +1,17 @@
 mod common;
 
+use core::{num::NonZeroUsize, ops::RangeBounds};
+
 use bytes::Bytes;
 use common::TestContext;
 use iavl::{
-	Get, MutableTree,
-	kvstore::{KVStore, MutKVStore},
+	Compression, Get, ImmutableTree, KeyChange, MutableTree,
+	kvstore::{
+		KVIterator, KVStore, MutKVStore,
+		faulty::{FaultyStore, Operation},
+		memory::{MemStore, MemStoreError},
+		redb::RedbStore,
+	},
 };
 use nebz::NonEmptyBz;
 use oblux::U63;
@@ -172,6 +179,12 @@ impl SaveExpected {
         SaveExpected::new(1, 0, "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"),
     ),
 )]
+#[case::save_tree_after_removing_every_saved_key(
+    vec![Op::insert("one", "plus"), Op::Save, Op::remove("one")],
+    Terminal::save(
+        SaveExpected::new(2, 0, "E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855"),
+    ),
+)]
 #[case::save_tree_with_one_unsaved_key(
     vec![Op::insert("first", "principle")],
     Terminal::save(
@@ -196,6 +209,19 @@ impl SaveExpected {
         SaveExpected::new(2, 2, "8CAD566B3364205E190849436169B33221AEA4D8756B26AA95501A428B7D3F96"),
     ),
 )]
+#[case::save_tree_after_removing_nonexistent_key_below_unsaved_node(
+    vec![
+        Op::insert("london", "wheel"),
+        Op::insert("dublin", "spire"),
+        Op::insert("chicago", "bean"),
+        Op::Save,
+        Op::insert("paris", "tower"),
+        Op::remove("oslo"),
+    ],
+    Terminal::save(
+        SaveExpected::new(2, 4, "BD1F3511D0EBA3FC5F79EF0D2A910A48F8C34DB315402A0B9A571C5C3B88B5C2"),
+    ),
+)]
 #[case::rr_heavy_leading_to_rotation_while_insertion_followed_by_save(
     vec![
         Op::insert("a", "a"),
@@ -360,3 +386,914 @@ where
 		},
 	}
 }
+
+#[test]
+fn get_versioned_reads_every_saved_version() {
+	// Arrange
+	let mut tree = TestContext::new().tree;
+
+	(0..16u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	exec_operation(&mut tree, Op::Save);
+
+	exec_operation(&mut tree, Op::insert([3], "updated"));
+	exec_operation(&mut tree, Op::remove([7]));
+	exec_operation(&mut tree, Op::Save);
+
+	exec_operation(&mut tree, Op::Save);
+
+	(0..16u8).for_each(|i| exec_operation(&mut tree, Op::remove([i])));
+	exec_operation(&mut tree, Op::Save);
+
+	let version = |v| U63::new(v).unwrap();
+
+	// Act & Assert
+	let (idx, value) = tree.get_versioned(utils::make_nebz_bytes([3]), version(1)).unwrap();
+	assert_eq!((idx.get(), value), (3, Some(Bytes::from_static(&[3]))));
+
+	let (idx, value) = tree.get_versioned(utils::make_nebz_bytes([7]), version(1)).unwrap();
+	assert_eq!((idx.get(), value), (7, Some(Bytes::from_static(&[7]))));
+
+	for v in [2, 3] {
+		let (idx, value) = tree.get_versioned(utils::make_nebz_bytes([3]), version(v)).unwrap();
+		assert_eq!(
+			(idx.get(), value),
+			(3, Some(Bytes::from_static(b"updated")))
+		);
+
+		let (_, value) = tree.get_versioned(utils::make_nebz_bytes([7]), version(v)).unwrap();
+		assert_eq!(value, None);
+	}
+
+	let (_, value) = tree.get_versioned(utils::make_nebz_bytes([3]), version(4)).unwrap();
+	assert_eq!(value, None);
+	assert_eq!(
+		const_hex::encode_upper(tree.saved_hash()),
+		"E3B0C44298FC1C149AFBF4C8996FB92427AE41E4649B934CA495991B7852B855",
+	);
+
+	assert!(tree.get_versioned(utils::make_nebz_bytes([3]), version(5)).is_err());
+}
+
+#[test]
+fn load_latest_version_restores_saved_tree() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	(0..16u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	exec_operation(&mut tree, Op::Save);
+
+	// Act
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	// Assert
+	assert_eq!(loaded.version(), tree.version());
+	assert_eq!(loaded.size(), tree.size());
+	assert_eq!(loaded.saved_hash(), tree.saved_hash());
+
+	for i in 0..16u8 {
+		let (idx, value) = loaded.get(utils::make_nebz_bytes([i])).unwrap();
+		assert_eq!(
+			(idx.get(), value),
+			(u64::from(i), Some(Bytes::copy_from_slice(&[i])))
+		);
+	}
+}
+
+#[test]
+fn load_latest_version_keeps_version_of_empty_latest_root() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	exec_operation(&mut tree, Op::insert("one", "plus"));
+	exec_operation(&mut tree, Op::Save);
+	exec_operation(&mut tree, Op::remove("one"));
+	exec_operation(&mut tree, Op::Save);
+
+	// Act
+	let mut loaded = MutableTree::load_latest_version(store).unwrap();
+
+	// Assert
+	assert_eq!(loaded.version(), tree.version());
+	assert_eq!(loaded.save().unwrap().get(), 3);
+
+	let (_, value) = loaded.get_versioned(utils::make_nebz_bytes("one"), U63::ONE).unwrap();
+	assert_eq!(value, Some(Bytes::from_static(b"plus")));
+}
+
+#[rstest]
+#[case::uncommitted(false)]
+#[case::unmarked(true)]
+fn load_latest_version_discards_half_saved_version(#[case] unmarked: bool) {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	(0..16u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	exec_operation(&mut tree, Op::Save);
+	let saved_hash = tree.saved_hash();
+
+	(16..24u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	tree.save_with_metadata(utils::make_nebz_bytes("half")).unwrap();
+	let half_saved_hash = tree.saved_hash();
+
+	let key = |prefix: u8, version: u64, nonce: Option<u32>| {
+		let mut key = [[prefix].as_slice(), &version.to_be_bytes()].concat();
+		key.extend(nonce.map(u32::to_be_bytes).into_iter().flatten());
+		utils::make_nebz_bytes(key)
+	};
+
+	// a half-saved version lacks its commit marker, whereas an older one lacks its root as well
+	assert!(store.remove(key(b'c', 2, None)).unwrap());
+
+	if unmarked {
+		assert!(store.remove(key(b'c', 1, None)).unwrap());
+		assert!(store.remove(key(b's', 2, Some(1))).unwrap());
+	}
+
+	// Act
+	let mut loaded = MutableTree::load_latest_version(store.clone()).unwrap();
+
+	// Assert
+	assert_eq!(loaded.version().get(), 1);
+	assert_eq!(loaded.saved_hash(), saved_hash);
+
+	assert!(!store.has(key(b's', 2, Some(2))).unwrap());
+	assert!(!store.has(key(b'v', 2, None)).unwrap());
+
+	(16..24u8).for_each(|i| exec_operation(&mut loaded, Op::insert([i], [i])));
+	assert_eq!(loaded.save().unwrap().get(), 2);
+	assert_eq!(loaded.saved_hash(), half_saved_hash);
+	assert_eq!(loaded.version_metadata(loaded.version()).unwrap(), None);
+}
+
+#[test]
+fn load_latest_version_read_only_skips_half_saved_version() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	(0..16u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	exec_operation(&mut tree, Op::Save);
+	let saved_hash = tree.saved_hash();
+
+	(16..24u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i])));
+	exec_operation(&mut tree, Op::Save);
+
+	let marker_key = utils::make_nebz_bytes([[b'c'].as_slice(), &2u64.to_be_bytes()].concat());
+	assert!(store.remove(marker_key).unwrap());
+
+	// a snapshot offers no writes, so the tree cannot write either
+	let snapshot = store.snapshot().unwrap();
+
+	// Act
+	let loaded = MutableTree::load_latest_version_read_only(snapshot.clone()).unwrap();
+	let immutable = ImmutableTree::load_latest_version(snapshot).unwrap().unwrap();
+
+	// Assert
+	assert_eq!(loaded.version().get(), 1);
+	assert_eq!(loaded.saved_hash(), saved_hash);
+	assert_eq!(immutable.version().get(), 1);
+	assert_eq!(immutable.hash(), saved_hash);
+
+	let (_, value) = immutable.get(utils::make_nebz_bytes([7])).unwrap();
+	assert_eq!(value, Some(Bytes::from_static(&[7])));
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([16])).unwrap();
+	assert_eq!(value, None);
+
+	// the half-saved version is left in place
+	let root_key = [[b's'].as_slice(), &2u64.to_be_bytes(), &1u32.to_be_bytes()].concat();
+	assert!(store.has(utils::make_nebz_bytes(root_key)).unwrap());
+}
+
+#[test]
+fn load_latest_version_read_only_yields_no_immutable_tree_for_empty_store() {
+	// Arrange
+	let store = TestContext::new().store.snapshot().unwrap();
+
+	// Act
+	let loaded = ImmutableTree::load_latest_version(store).unwrap();
+
+	// Assert
+	assert!(loaded.is_none());
+}
+
+#[test]
+fn save_with_metadata_stores_metadata_against_version() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	exec_operation(&mut tree, Op::insert("block", "one"));
+
+	// Act
+	let first = tree.save_with_metadata(utils::make_nebz_bytes("hash-1")).unwrap();
+	let second = tree.save().unwrap();
+
+	exec_operation(&mut tree, Op::remove("block"));
+	let third = tree.save_with_metadata(utils::make_nebz_bytes("hash-3")).unwrap();
+
+	// Assert
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	let metadata = |v| loaded.version_metadata(v).unwrap().map(NonEmptyBz::into_inner);
+
+	assert_eq!(metadata(first), Some(Bytes::from_static(b"hash-1")));
+	assert_eq!(metadata(second), None);
+	assert_eq!(metadata(third), Some(Bytes::from_static(b"hash-3")));
+	assert_eq!(loaded.version(), third);
+}
+
+#[test]
+fn roots_yield_saved_hashes_of_every_version() {
+	// Arrange
+	let mut tree = TestContext::new().tree;
+
+	let ops = [
+		vec![],
+		vec![Op::insert("alpha", "1")],
+		vec![Op::insert("beta", "2"), Op::insert("gamma", "3")],
+		vec![],
+		vec![Op::remove("beta")],
+		vec![Op::remove("alpha"), Op::remove("gamma")],
+	];
+
+	let expected = ops
+		.into_iter()
+		.map(|ops| {
+			ops.into_iter().for_each(|op| exec_operation(&mut tree, op));
+			(tree.save().unwrap(), tree.saved_hash())
+		})
+		.collect::<Vec<_>>();
+
+	// Act
+	let roots = tree.roots(..).collect::<Result<Vec<_>, _>>().unwrap();
+	let bounded_roots =
+		tree.roots(U63::TWO..U63::new(4).unwrap()).collect::<Result<Vec<_>, _>>().unwrap();
+
+	// Assert
+	assert_eq!(roots, expected);
+	assert_eq!(bounded_roots, expected[1..3]);
+
+	for (version, hash) in expected {
+		assert_eq!(tree.root_hash(version).unwrap(), Some(hash));
+	}
+
+	assert_eq!(tree.root_hash(U63::new(7).unwrap()).unwrap(), None);
+}
+
+#[test]
+fn roots_read_commit_markers_at_once_and_check_them_against_root_entries() {
+	// Arrange
+	let store = FaultyStore::new(MemStore::new());
+	let mut tree = MutableTree::new(store.clone());
+
+	let expected = (0..8u8)
+		.map(|i| {
+			exec_operation(&mut tree, Op::insert([i], [i]));
+			(tree.save().unwrap(), tree.saved_hash())
+		})
+		.collect::<Vec<_>>();
+
+	// leaves the first two versions as if saved before commit markers were introduced
+	for version in 1..=2u64 {
+		let marker_key =
+			utils::make_nebz_bytes([[b'c'].as_slice(), &version.to_be_bytes()].concat());
+		assert!(store.inner().remove(marker_key).unwrap());
+	}
+
+	let gets = store.calls(Operation::Get).unwrap();
+
+	// Act
+	let roots = tree.roots(..).collect::<Result<Vec<_>, _>>().unwrap();
+
+	// Assert
+	assert_eq!(roots, expected);
+	assert_eq!(store.calls(Operation::Get).unwrap() - gets, 8);
+}
+
+#[test]
+fn roots_reject_commit_markers_disagreeing_with_root_entries() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+
+	for i in 0..3u8 {
+		exec_operation(&mut tree, Op::insert([i], [i]));
+		exec_operation(&mut tree, Op::Save);
+	}
+
+	let marker_key = utils::make_nebz_bytes([[b'c'].as_slice(), &2u64.to_be_bytes()].concat());
+
+	// Act
+	store.insert(marker_key, utils::make_nebz_bytes([0; 32])).unwrap();
+
+	// Assert
+	let roots = tree.roots(..).collect::<Vec<_>>();
+
+	assert!(roots[0].is_ok());
+	assert!(roots[1].as_ref().unwrap_err().to_string().contains("commit mismatch"));
+	assert!(roots[2].is_ok());
+}
+
+#[test]
+fn root_lookups_ignore_root_entries_past_the_latest_version() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+
+	exec_operation(&mut tree, Op::insert([0], [0]));
+	let version = tree.save().unwrap();
+
+	let root_key = |version: u64| {
+		utils::make_nebz_bytes(
+			[
+				[b's'].as_slice(),
+				&version.to_be_bytes(),
+				&1u32.to_be_bytes(),
+			]
+			.concat(),
+		)
+	};
+	let root = store.get(root_key(1)).unwrap().unwrap();
+
+	// Act
+	store.insert(root_key(2), root).unwrap();
+
+	// Assert
+	let half_saved = U63::new(2).unwrap();
+
+	assert!(tree.root_hash(version).unwrap().is_some());
+	assert_eq!(tree.root_hash(half_saved).unwrap(), None);
+	assert!(tree.get_versioned(utils::make_nebz_bytes([0]), half_saved).is_err());
+}
+
+#[test]
+fn checkpoints_tag_saved_versions() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	exec_operation(&mut tree, Op::insert("chain", "v6"));
+	let v1 = tree.save().unwrap();
+
+	exec_operation(&mut tree, Op::insert("chain", "v7"));
+	let v2 = tree.save().unwrap();
+
+	let tag = |tag| utils::make_nebz_bytes(tag);
+
+	// Act
+	tree.checkpoint(tag("pre-upgrade-v7"), v1).unwrap();
+	tree.checkpoint(tag("pre-upgrade-v7"), v1).unwrap();
+	tree.checkpoint(tag("post-upgrade-v7"), v2).unwrap();
+
+	// Assert
+	assert!(tree.checkpoint(tag("pre-upgrade-v7"), v2).is_err());
+	assert!(tree.checkpoint(tag("future"), U63::new(3).unwrap()).is_err());
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	assert_eq!(
+		loaded.checkpoint_version(tag("pre-upgrade-v7")).unwrap(),
+		Some(v1)
+	);
+	assert_eq!(loaded.checkpoint_version(tag("future")).unwrap(), None);
+	assert_eq!(
+		loaded.checkpoints().unwrap(),
+		vec![(tag("post-upgrade-v7"), v2), (tag("pre-upgrade-v7"), v1)],
+	);
+	assert!(loaded.is_checkpointed(v1).unwrap());
+
+	assert!(loaded.remove_checkpoint(tag("pre-upgrade-v7")).unwrap());
+	assert!(!loaded.remove_checkpoint(tag("pre-upgrade-v7")).unwrap());
+	assert!(!loaded.is_checkpointed(v1).unwrap());
+	assert_eq!(loaded.version(), v2);
+}
+
+#[test]
+fn checkpoint_rejects_version_without_commit_marker() {
+	// Arrange
+	let TestContext { mut tree, store } = TestContext::new();
+
+	exec_operation(&mut tree, Op::insert("chain", "v6"));
+	let v1 = tree.save().unwrap();
+
+	exec_operation(&mut tree, Op::insert("chain", "v7"));
+	let v2 = tree.save().unwrap();
+
+	// leaves version 2 with every node, root included, but no commit marker as if torn
+	let marker_key = utils::make_nebz_bytes([[b'c'].as_slice(), &2u64.to_be_bytes()].concat());
+	assert!(store.remove(marker_key).unwrap());
+
+	let tag = |tag| utils::make_nebz_bytes(tag);
+
+	// Act
+	let torn = tree.checkpoint(tag("torn"), v2);
+	let saved = tree.checkpoint(tag("saved"), v1);
+
+	// Assert
+	assert!(torn.is_err());
+	assert!(saved.is_ok());
+	assert_eq!(tree.checkpoint_version(tag("torn")).unwrap(), None);
+	assert_eq!(tree.checkpoint_version(tag("saved")).unwrap(), Some(v1));
+}
+
+#[test]
+fn key_history_lists_versions_changing_key() {
+	// Arrange
+	let mut tree = TestContext::new().tree;
+
+	let ops = [
+		vec![Op::insert("account", "10")],
+		vec![Op::insert("other", "1")],
+		vec![Op::insert("account", "20")],
+		vec![Op::remove("account")],
+		vec![],
+		vec![Op::insert("another", "2")],
+		vec![Op::insert("account", "30")],
+	];
+
+	ops.into_iter().for_each(|ops| {
+		ops.into_iter().chain([Op::Save]).for_each(|op| exec_operation(&mut tree, op));
+	});
+
+	let key = utils::make_nebz_bytes("account");
+	let version = |v| U63::new(v).unwrap();
+
+	// Act
+	let history = tree.key_history(key.as_ref_slice(), ..).unwrap();
+	let bounded_history = tree.key_history(key.as_ref_slice(), version(2)..=version(4)).unwrap();
+	let trailing_history = tree.key_history(key.as_ref_slice(), version(5)..).unwrap();
+
+	// Assert
+	assert_eq!(
+		history,
+		vec![
+			(version(1), KeyChange::Written),
+			(version(3), KeyChange::Written),
+			(version(4), KeyChange::Deleted),
+			(version(7), KeyChange::Written),
+		],
+	);
+	assert_eq!(
+		bounded_history,
+		vec![
+			(version(3), KeyChange::Written),
+			(version(4), KeyChange::Deleted)
+		],
+	);
+	assert_eq!(trailing_history, vec![(version(7), KeyChange::Written)]);
+}
+
+#[test]
+fn get_with_meta_yields_leaf_version_and_node_key() {
+	// Arrange
+	let mut tree = TestContext::new().tree;
+
+	exec_operation(&mut tree, Op::insert("stable", "1"));
+	exec_operation(&mut tree, Op::insert("volatile", "1"));
+	exec_operation(&mut tree, Op::Save);
+
+	exec_operation(&mut tree, Op::insert("volatile", "2"));
+	exec_operation(&mut tree, Op::Save);
+
+	exec_operation(&mut tree, Op::insert("volatile", "unsaved"));
+
+	// Act
+	let (stable_idx, stable) = tree.get_with_meta(utils::make_nebz_bytes("stable")).unwrap();
+	let (volatile_idx, volatile) =
+		tree.last_saved().unwrap().get_with_meta(utils::make_nebz_bytes("volatile")).unwrap();
+	let (_, missing) = tree.get_with_meta(utils::make_nebz_bytes("missing")).unwrap();
+
+	// Assert
+	let (value, version, nk) = stable.unwrap();
+	assert_eq!(
+		(stable_idx.get(), value, version.get()),
+		(0, Bytes::from_static(b"1"), 1)
+	);
+	assert_eq!(nk.version(), &version);
+
+	let (value, version, nk) = volatile.unwrap();
+	assert_eq!(
+		(volatile_idx.get(), value, version.get()),
+		(1, Bytes::from_static(b"2"), 2)
+	);
+	assert_eq!(nk.version(), &version);
+
+	assert!(missing.is_none());
+}
+
+#[rstest]
+#[case::zstd(Compression::Zstd { level: 3 })]
+#[case::lz4(Compression::Lz4)]
+fn compressed_nodes_keep_hashes_and_read_back(#[case] compression: Compression) {
+	// Arrange
+	let TestContext { tree: mut plain_tree, store: plain_store } = TestContext::new();
+	let TestContext { mut tree, store } = TestContext::new();
+
+	let value = |i: u8| format!(r#"{{"account":{i},"memo":"{}"}}"#, "paid ".repeat(64));
+
+	let stored_len = |store: &RedbStore| {
+		KVIterator::iter(store, ..).unwrap().map(|kv| kv.unwrap().1.len().get()).sum::<usize>()
+	};
+
+	for t in [&mut plain_tree, &mut tree] {
+		(0..16u8).for_each(|i| exec_operation(t, Op::insert([i], value(i))));
+		exec_operation(t, Op::Save);
+	}
+
+	// Act
+	tree.set_compression(Some(compression));
+
+	for t in [&mut plain_tree, &mut tree] {
+		(16..32u8).for_each(|i| exec_operation(t, Op::insert([i], value(i))));
+		exec_operation(t, Op::Save);
+	}
+
+	// Assert
+	assert_eq!(tree.saved_hash(), plain_tree.saved_hash());
+	assert!(stored_len(&store) < stored_len(&plain_store) * 3 / 4);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.saved_hash(), plain_tree.saved_hash());
+
+	for i in 0..32u8 {
+		let (_, loaded_value) = loaded.get(utils::make_nebz_bytes([i])).unwrap();
+		assert_eq!(loaded_value, Some(Bytes::from(value(i))));
+	}
+}
+
+#[test]
+fn checksums_expose_corrupted_nodes() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+	tree.set_checksums(true);
+
+	(0..8u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], format!("value {i}"))));
+	exec_operation(&mut tree, Op::Save);
+
+	let (leaf_key, mut leaf) = KVIterator::iter(&store, ..)
+		.unwrap()
+		.map(Result::unwrap)
+		.find(|(_, v)| v.get().windows(7).any(|w| w == b"value 3"))
+		.map(|(k, v)| (k, v.get().to_vec()))
+		.unwrap();
+
+	// Act
+	let corrupted_at = leaf.len() - 6;
+	leaf[corrupted_at] ^= 1;
+	store.insert(leaf_key, NonEmptyBz::new(leaf).unwrap()).unwrap();
+
+	// Assert
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	let err = loaded.get(utils::make_nebz_bytes([3])).unwrap_err();
+	assert!(err.to_string().contains("checksum mismatch"), "{err}");
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([4])).unwrap();
+	assert_eq!(value, Some(Bytes::from_static(b"value 4")));
+}
+
+#[test]
+fn lz4_nodes_with_inflated_length_prefix_are_rejected() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+	tree.set_compression(Some(Compression::Lz4));
+
+	exec_operation(&mut tree, Op::insert([0], "paid"));
+	exec_operation(&mut tree, Op::insert([1], "paid ".repeat(256)));
+	exec_operation(&mut tree, Op::Save);
+
+	let (leaf_key, mut leaf) = KVIterator::iter(&store, ..)
+		.unwrap()
+		.map(Result::unwrap)
+		.find(|(k, v)| k.get()[0] == b's' && v.get()[0] == 0xFD)
+		.map(|(k, v)| (k, v.get().to_vec()))
+		.unwrap();
+
+	// Act
+	let inflated_len = u32::try_from((leaf.len() - 5) * 255 + 1).unwrap();
+	leaf[1..5].copy_from_slice(&inflated_len.to_le_bytes());
+	store.insert(leaf_key, NonEmptyBz::new(leaf).unwrap()).unwrap();
+
+	// Assert
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	let err = loaded.get(utils::make_nebz_bytes([1])).unwrap_err();
+	assert!(err.to_string().contains("LZ4 ratio"), "{err}");
+}
+
+#[test]
+fn zstd_nodes_beyond_the_ratio_cap_are_saved_uncompressed() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+	tree.set_compression(Some(Compression::Zstd { level: 3 }));
+
+	let value = Bytes::from(vec![0; 1 << 16]);
+
+	// Act
+	exec_operation(&mut tree, Op::insert([0], value.clone()));
+	exec_operation(&mut tree, Op::Save);
+
+	// Assert
+	let compressed = KVIterator::iter(&store, ..)
+		.unwrap()
+		.map(Result::unwrap)
+		.any(|(k, v)| k.get()[0] == b's' && v.get()[0] == 0xFE);
+	assert!(!compressed);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+
+	let (_, loaded_value) = loaded.get(utils::make_nebz_bytes([0])).unwrap();
+	assert_eq!(loaded_value, Some(value));
+}
+
+#[test]
+fn separated_values_keep_hashes_and_are_stored_once() {
+	// Arrange
+	let large_value = Bytes::from(vec![7; 256]);
+
+	let mut plain_tree = MutableTree::new(MemStore::new());
+
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+	tree.set_value_separation(NonZeroUsize::new(64));
+
+	// Act
+	for round in 0..4u8 {
+		for i in 0..8u8 {
+			let value = if i % 2 == 0 {
+				large_value.clone()
+			} else {
+				Bytes::from(vec![round; 8])
+			};
+
+			exec_operation(&mut plain_tree, Op::insert([i], value.clone()));
+			exec_operation(&mut tree, Op::insert([i], value));
+		}
+
+		exec_operation(&mut plain_tree, Op::Save);
+		exec_operation(&mut tree, Op::Save);
+	}
+
+	// Assert
+	assert_eq!(tree.saved_hash(), plain_tree.saved_hash());
+
+	let separated_values = KVIterator::iter(&store, ..)
+		.unwrap()
+		.map(Result::unwrap)
+		.filter(|(k, _)| k.get()[0] == b'b')
+		.collect::<Vec<_>>();
+
+	assert_eq!(separated_values.len(), 1);
+	assert_eq!(separated_values[0].1.get(), &large_value);
+
+	let loaded = MutableTree::load_latest_version(store).unwrap();
+	assert_eq!(loaded.saved_hash(), plain_tree.saved_hash());
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([2])).unwrap();
+	assert_eq!(value, Some(large_value));
+
+	let (_, value) = loaded.get(utils::make_nebz_bytes([3])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![3; 8])));
+}
+
+#[test]
+fn copy_to_moves_every_version_between_backends() {
+	// Arrange
+	let TestContext { mut tree, .. } = TestContext::new();
+	tree.set_checksums(true);
+	tree.set_value_separation(NonZeroUsize::new(32));
+
+	for version in 0..4u16 {
+		for i in 0..400u16 {
+			let key = [(i >> 8) as u8, i as u8];
+			exec_operation(
+				&mut tree,
+				Op::insert(key, [version.to_be_bytes(), key].concat()),
+			);
+		}
+
+		exec_operation(&mut tree, Op::insert([0xFF], [version as u8; 64]));
+		exec_operation(&mut tree, Op::Save);
+	}
+
+	tree.checkpoint(utils::make_nebz_bytes(b"two"), U63::new(2).unwrap()).unwrap();
+
+	// Act
+	let copied = tree.copy_to(MemStore::new()).unwrap();
+	let copied_back = copied.copy_to(TestContext::new().store).unwrap();
+
+	// Assert
+	let roots = tree.roots(..).collect::<Result<Vec<_>, _>>().unwrap();
+	assert_eq!(
+		copied.roots(..).collect::<Result<Vec<_>, _>>().unwrap(),
+		roots
+	);
+	assert_eq!(
+		copied_back.roots(..).collect::<Result<Vec<_>, _>>().unwrap(),
+		roots
+	);
+
+	assert_eq!(copied_back.version().get(), 4);
+	assert_eq!(copied_back.saved_hash(), tree.saved_hash());
+	assert_eq!(
+		copied_back.checkpoints().unwrap(),
+		tree.checkpoints().unwrap()
+	);
+
+	let (_, value) =
+		copied_back.get_versioned(utils::make_nebz_bytes([1, 2]), U63::new(2).unwrap()).unwrap();
+	assert_eq!(value, Some(Bytes::from_static(&[0, 1, 1, 2])));
+
+	let (_, value) = copied_back.get(utils::make_nebz_bytes([0xFF])).unwrap();
+	assert_eq!(value, Some(Bytes::from(vec![3; 64])));
+}
+
+#[test]
+fn copy_to_rejects_target_losing_a_node() {
+	// Arrange
+	let mut tree = MutableTree::new(MemStore::new());
+
+	for i in 0..3u8 {
+		exec_operation(&mut tree, Op::insert([i], "one"));
+	}
+
+	exec_operation(&mut tree, Op::Save);
+
+	// any node of version 1 below its root
+	let dropped = [[b's'].as_slice(), &1u64.to_be_bytes(), &2u32.to_be_bytes()].concat();
+	let target = DroppingStore { store: MemStore::new(), dropped: Bytes::from(dropped) };
+
+	// Act
+	let Err(err) = tree.copy_to(target) else {
+		panic!("copying must fail");
+	};
+
+	// Assert
+	assert!(
+		err.to_string().contains("copy mismatch error: version 1"),
+		"{err}"
+	);
+}
+
+/// A store silently dropping the writes of key `dropped`.
+#[derive(Clone)]
+struct DroppingStore {
+	store: MemStore,
+	dropped: Bytes,
+}
+
+impl KVStore for DroppingStore {
+	type Error = MemStoreError;
+
+	type Snapshot = MemStore;
+
+	fn snapshot(&self) -> Result<Self::Snapshot, Self::Error> {
+		self.store.snapshot()
+	}
+
+	fn get<K>(&self, key: NonEmptyBz<K>) -> Result<Option<NonEmptyBz<Bytes>>, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.get(key)
+	}
+}
+
+impl MutKVStore for DroppingStore {
+	type Error = MemStoreError;
+
+	fn insert<K, V>(&self, key: NonEmptyBz<K>, value: NonEmptyBz<V>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+		V: AsRef<[u8]>,
+	{
+		if key.get().as_ref() == self.dropped {
+			return Ok(false);
+		}
+
+		self.store.insert(key, value)
+	}
+
+	fn remove<K>(&self, key: NonEmptyBz<K>) -> Result<bool, Self::Error>
+	where
+		K: AsRef<[u8]>,
+	{
+		self.store.remove(key)
+	}
+}
+
+impl KVIterator for DroppingStore {
+	type Error = MemStoreError;
+
+	type FetchError = MemStoreError;
+
+	fn iter<'a, KR>(
+		&self,
+		range: KR,
+	) -> Result<
+		impl DoubleEndedIterator<
+			Item = Result<(NonEmptyBz<Bytes>, NonEmptyBz<Bytes>), Self::FetchError>,
+		>,
+		Self::Error,
+	>
+	where
+		KR: RangeBounds<NonEmptyBz<&'a [u8]>>,
+	{
+		self.store.iter(range)
+	}
+}
+
+#[test]
+fn copy_to_rejects_target_holding_other_versions() {
+	// Arrange
+	let mut tree = MutableTree::new(MemStore::new());
+	exec_operation(&mut tree, Op::insert([1], "one"));
+	exec_operation(&mut tree, Op::Save);
+
+	let target = MemStore::new();
+	let mut other_tree = MutableTree::new(target.clone());
+
+	for i in 0..2u8 {
+		exec_operation(&mut other_tree, Op::insert([i], "other"));
+		exec_operation(&mut other_tree, Op::Save);
+	}
+
+	// Act
+	let Err(err) = tree.copy_to(target) else {
+		panic!("copying must fail");
+	};
+
+	// Assert
+	assert!(
+		err.to_string().contains("copy mismatch error: version 2"),
+		"{err}"
+	);
+}
+
+#[test]
+fn node_cache_serves_repeated_reads_without_store() {
+	// Arrange
+	let store = FaultyStore::new(MemStore::new());
+	let mut tree = MutableTree::new(store.clone());
+
+	(0..64u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i; 8])));
+	exec_operation(&mut tree, Op::Save);
+
+	let mut tree = MutableTree::load_latest_version(store.clone()).unwrap();
+	tree.set_node_cache(NonZeroUsize::new(1 << 20));
+
+	let read_all = |tree: &MutableTree<_>| {
+		for i in 0..64u8 {
+			let (_, value) = tree.get(utils::make_nebz_bytes([i])).unwrap();
+			assert_eq!(value, Some(Bytes::from(vec![i; 8])));
+		}
+	};
+
+	read_all(&tree);
+	let (gets, stats) = (
+		store.calls(Operation::Get).unwrap(),
+		tree.node_cache_stats().unwrap(),
+	);
+
+	// Act
+	read_all(&tree);
+
+	// Assert
+	assert_eq!(store.calls(Operation::Get).unwrap(), gets);
+
+	let (stats, cached_stats) = (stats.unwrap(), tree.node_cache_stats().unwrap().unwrap());
+	assert_eq!(stats.nodes, 126);
+	assert_eq!(cached_stats.misses, stats.misses);
+	assert_eq!(cached_stats.hits, stats.hits + 64 * 6);
+}
+
+#[test]
+fn node_cache_stays_within_byte_limit() {
+	// Arrange
+	let store = MemStore::new();
+	let mut tree = MutableTree::new(store.clone());
+
+	(0..64u8).for_each(|i| exec_operation(&mut tree, Op::insert([i], [i; 64])));
+	exec_operation(&mut tree, Op::Save);
+
+	let mut tree = MutableTree::load_latest_version(store).unwrap();
+
+	let max_bytes = NonZeroUsize::new(8192).unwrap();
+	tree.set_node_cache(Some(max_bytes));
+
+	// Act
+	for _ in 0..2 {
+		for i in 0..64u8 {
+			let (_, value) = tree.get(utils::make_nebz_bytes([i])).unwrap();
+			assert_eq!(value, Some(Bytes::from(vec![i; 64])));
+		}
+	}
+
+	// Assert
+	let stats = tree.node_cache_stats().unwrap().unwrap();
+	assert!(stats.bytes <= max_bytes.get());
+	assert!(stats.nodes > 0);
+	assert!(stats.hits > 0);
+	assert!(stats.misses > 64);
+}